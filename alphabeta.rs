@@ -0,0 +1,370 @@
+// --- Iterative-deepening alpha-beta wall player ---
+//
+// Minimax over walls and cat steps: the cat may take any empty neighbour, so a
+// proven win holds against every cat, not only Board::move_cat. Each iteration
+// searches one wall deeper, ordered by the previous principal variation, until
+// the time limit runs out or the result is proven.
+
+use crate::strategies::legal_cat_moves;
+use crate::{Board, Player, Position, C, N, R};
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+
+// Scores at or beyond this are proven wins (or losses, negated)
+const WIN: i32 = 1_000_000;
+const PROVEN: i32 = WIN - 1000;
+
+// How often (in nodes) the clock is checked
+const CLOCK_INTERVAL: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlphaBetaConfig {
+    pub time_limit: Duration,
+    pub max_depth: usize,      // In walls
+    pub max_candidates: usize, // Walls tried per node, best ordered first
+}
+
+impl Default for AlphaBetaConfig {
+    fn default() -> Self {
+        AlphaBetaConfig {
+            time_limit: Duration::from_secs(1),
+            max_depth: 20,
+            max_candidates: 16,
+        }
+    }
+}
+
+// Static features of a position with the walls to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Evaluation {
+    pub distance: usize, // min_cat_moves
+    pub paths: u64,      // Shortest escape paths, as counted by move_cat
+    pub region: usize,   // Cells the cat can reach, including its own
+    pub cut: usize,      // Walls needed to separate the cat from the edge
+}
+
+impl Evaluation {
+    pub fn of(board: &Board) -> Self {
+        Evaluation {
+            distance: board.min_cat_moves(),
+            paths: board.num_escape_paths(),
+            region: board.cat_region().size(),
+            cut: board.min_vertex_cut().size,
+        }
+    }
+
+    // Higher is better for the walls. The walls place one wall per cat step,
+    // so a cut no larger than the distance is usually winnable.
+    pub fn score(&self) -> i32 {
+        if self.distance == N {
+            return WIN;
+        }
+        if self.distance == 0 {
+            return -WIN;
+        }
+        let distance = self.distance as i32;
+        let cut = self.cut as i32;
+        let paths = (u64::BITS - self.paths.leading_zeros()) as i32; // ~log2
+        100 * (distance - cut) + 20 * distance - 10 * paths - self.region as i32 / 4
+    }
+}
+
+// Result of one completed iteration
+#[derive(Debug, Clone)]
+pub struct Iteration {
+    pub depth: usize,
+    pub score: i32,
+    pub nodes: usize,
+    pub elapsed: Duration,
+    pub pv: Vec<Position>, // Walls and cat steps, alternating
+}
+
+impl Iteration {
+    // Number of walls to a proven trap, if the score is a proven win
+    pub fn win_in(&self) -> Option<usize> {
+        (self.score >= PROVEN).then(|| (WIN - self.score) as usize)
+    }
+
+    pub fn is_proven_loss(&self) -> bool {
+        self.score <= -PROVEN
+    }
+}
+
+impl Display for Iteration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {} ", self.depth)?;
+        match self.win_in() {
+            Some(n) => write!(f, "score win {}", n)?,
+            None if self.is_proven_loss() => write!(f, "score loss")?,
+            None => write!(f, "score {}", self.score)?,
+        }
+        write!(f, " nodes {} time {} pv", self.nodes, self.elapsed.as_millis())?;
+        for p in &self.pv {
+            write!(f, " {},{}", p.r, p.c)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct AlphaBetaPlayer {
+    config: AlphaBetaConfig,
+}
+
+impl AlphaBetaPlayer {
+    pub fn new(config: AlphaBetaConfig) -> Self {
+        AlphaBetaPlayer { config }
+    }
+
+    // Iterative deepening until the time limit, a proven result or max_depth.
+    // Each completed iteration is passed to `on_iteration`; the last one is
+    // returned (None only if the walls have no legal move).
+    pub fn search(&self, board: &Board, on_iteration: &mut dyn FnMut(&Iteration)) -> Option<Iteration> {
+        let mut search = Search {
+            board: board.clone(),
+            deadline: Instant::now() + self.config.time_limit,
+            max_candidates: self.config.max_candidates,
+            nodes: 0,
+            aborted: false,
+            previous_pv: Vec::new(),
+        };
+        let start = Instant::now();
+        let mut best: Option<Iteration> = None;
+
+        for depth in 1..=self.config.max_depth {
+            let mut pv = Vec::new();
+            let score = search.max_node(depth, 0, -WIN - 1, WIN + 1, &mut pv);
+            if search.aborted || pv.is_empty() {
+                break;
+            }
+            let iteration = Iteration {
+                depth,
+                score,
+                nodes: search.nodes,
+                elapsed: start.elapsed(),
+                pv: pv.clone(),
+            };
+            on_iteration(&iteration);
+            search.previous_pv = pv;
+            best = Some(iteration);
+            if score.abs() >= PROVEN {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl Player for AlphaBetaPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let result = self.search(board, &mut |iteration| eprintln!("AlphaBetaPlayer {}", iteration));
+        // Without a completed iteration, fall back to the best-ordered candidate
+        result
+            .and_then(|iteration| iteration.pv.first().copied())
+            .or_else(|| candidate_walls(board, 1).first().copied())
+    }
+}
+
+struct Search {
+    board: Board, // Walls and the cat are placed and undone in place
+    deadline: Instant,
+    max_candidates: usize,
+    nodes: usize,
+    aborted: bool,
+    previous_pv: Vec<Position>, // Last principal variation, tried first at the same ply
+}
+
+impl Search {
+    fn out_of_time(&mut self) -> bool {
+        if !self.aborted && self.nodes.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= self.deadline {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    // Walls to move with `depth` walls left; `ply` counts walls placed so far
+    fn max_node(&mut self, depth: usize, ply: usize, mut alpha: i32, beta: i32, pv: &mut Vec<Position>) -> i32 {
+        self.nodes += 1;
+        if self.out_of_time() || depth == 0 {
+            return Evaluation::of(&self.board).score();
+        }
+
+        let mut walls = candidate_walls(&self.board, self.max_candidates);
+        if let Some(&p) = self.previous_pv.get(2 * ply) {
+            if let Some(i) = walls.iter().position(|&w| w == p) {
+                let p = walls.remove(i);
+                walls.insert(0, p);
+            }
+        }
+
+        let mut best = -WIN - 1;
+        let mut line = Vec::new();
+        for wall in walls {
+            self.board.cells.set(wall.idx(), true);
+            line.clear();
+            let score = if self.board.min_cat_moves() == N {
+                WIN - ply as i32 - 1
+            } else {
+                self.min_node(depth, ply, alpha, beta, &mut line)
+            };
+            self.board.cells.set(wall.idx(), false);
+            if self.aborted {
+                return best;
+            }
+
+            if score > best {
+                best = score;
+                pv.clear();
+                pv.push(wall);
+                pv.extend_from_slice(&line);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    // Cat to move after the walls' `ply`-th wall
+    fn min_node(&mut self, depth: usize, ply: usize, alpha: i32, mut beta: i32, pv: &mut Vec<Position>) -> i32 {
+        let cat = self.board.cat_position;
+        let mut steps = legal_cat_moves(&self.board);
+        // Steps towards the edge first, move_cat's choice leading
+        let preferred = self.board.best_cat_move();
+        steps.sort_by_key(|&p| {
+            let mut next = self.board.clone();
+            next.cat_position = p;
+            (Some(p) != preferred, next.min_cat_moves())
+        });
+        if let Some(&p) = self.previous_pv.get(2 * ply + 1) {
+            if let Some(i) = steps.iter().position(|&s| s == p) {
+                let p = steps.remove(i);
+                steps.insert(0, p);
+            }
+        }
+
+        let mut best = WIN + 1;
+        let mut line = Vec::new();
+        for step in steps {
+            self.board.cat_position = step;
+            line.clear();
+            let score = if self.board.is_exit(step) {
+                -WIN + ply as i32 + 1
+            } else {
+                self.max_node(depth - 1, ply + 1, alpha, beta, &mut line)
+            };
+            self.board.cat_position = cat;
+            if self.aborted {
+                return best;
+            }
+
+            if score < best {
+                best = score;
+                pv.clear();
+                pv.push(step);
+                pv.extend_from_slice(&line);
+            }
+            beta = beta.min(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+// BFS distances over empty cells from the given sources (usize::MAX if unreachable)
+fn distances_from(board: &Board, sources: &[Position]) -> Vec<usize> {
+    let mut distance = vec![usize::MAX; N];
+    let mut queue = VecDeque::new();
+    for &p in sources {
+        if !board.cells[p.idx()] && distance[p.idx()] == usize::MAX {
+            distance[p.idx()] = 0;
+            queue.push_back(p);
+        }
+    }
+    while let Some(p) = queue.pop_front() {
+        for &q in board.neighbors(p) {
+            if !board.cells[q.idx()] && distance[q.idx()] == usize::MAX {
+                distance[q.idx()] = distance[p.idx()] + 1;
+                queue.push_back(q);
+            }
+        }
+    }
+    distance
+}
+
+// Walls worth trying, best first: cells of a minimum cut, then cells on the
+// cat's shortest escape paths, then cells near the cat, each closest to the
+// cat first
+fn candidate_walls(board: &Board, limit: usize) -> Vec<Position> {
+    let cut = board.min_vertex_cut().cells;
+    let from_cat = distances_from(board, &[board.cat_position]);
+    let from_edge = board.edge_distance_field().map(|d| d.unwrap_or(usize::MAX));
+    let distance = from_edge[board.cat_position];
+
+    let mut walls = Vec::new();
+    for r in 0..R {
+        for c in 0..C {
+            let p = Position::new(r, c);
+            let (d_cat, d_edge) = (from_cat[p.idx()], from_edge[p]);
+            if board.cells[p.idx()] || p == board.cat_position || d_cat == usize::MAX {
+                continue;
+            }
+            let on_path = d_edge != usize::MAX && d_cat + d_edge == distance;
+            let in_cut = cut.contains(&p);
+            if in_cut || on_path || d_cat <= 2 {
+                walls.push((!in_cut, !on_path, d_cat, p));
+            }
+        }
+    }
+    walls.sort();
+    walls.into_iter().take(limit).map(|(_, _, _, p)| p).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cat at (5,5) has two ways out, (5,4) into the open and (5,6),
+    // which leads on only through (5,7). Walling (5,4) forces the cat into
+    // (5,6), and walling (5,7) then traps it; walling either cell of the
+    // other way first lets it out through (5,4).
+    #[test]
+    fn alphabeta_deepens_to_a_win_in_two() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        for (r, c) in [(4, 5), (4, 6), (6, 6), (6, 5), (4, 7), (6, 7)] {
+            board.cells.set(Position::new(r, c).idx(), true);
+        }
+        let player = AlphaBetaPlayer::new(AlphaBetaConfig::default());
+        let mut depths = Vec::new();
+        let iteration = player
+            .search(&board, &mut |iteration| depths.push(iteration.depth))
+            .unwrap();
+        assert_eq!(depths, [1, 2]);
+        assert_eq!(iteration.win_in(), Some(2));
+        let line = [(5, 4), (5, 6), (5, 7)].map(|(r, c)| Position::new(r, c));
+        assert_eq!(iteration.pv, line);
+    }
+
+    // An open board is never proven, so only the clock ends the search
+    #[test]
+    fn alphabeta_stops_at_its_time_limit() {
+        let board = Board::random(32);
+        let config = AlphaBetaConfig {
+            time_limit: Duration::from_millis(20),
+            ..AlphaBetaConfig::default()
+        };
+        let start = Instant::now();
+        let iteration = AlphaBetaPlayer::new(config).search(&board, &mut |_| {});
+        assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+        if let Some(iteration) = iteration {
+            assert!(iteration.depth < config.max_depth);
+            assert!(iteration.win_in().is_none());
+        }
+        let wall = AlphaBetaPlayer::new(config).get_move(&board).unwrap();
+        assert!(!board.cells[wall.idx()] && wall != board.cat_position);
+    }
+}
@@ -0,0 +1,289 @@
+// --- Post-game analysis ---
+//
+// Replays a finished game record and, before every wall, asks the AutoPlayer
+// search for the best wall. The played wall and the best one are both judged
+// on the position after them and the cat's `move_cat` reply, searched with the
+// same budget, so a wall is only called worse than the best when the solver
+// can tell them apart.
+
+use crate::alphabeta::Evaluation;
+use crate::game::{Game, GameRecord, GameStatus, MoveError};
+use crate::{AutoPlayer, Board, Position, SearchBudget};
+use std::cmp::{Ordering, Reverse};
+use std::fmt::{self, Display};
+use std::sync::atomic::AtomicBool;
+
+// Score drops (in Evaluation::score units) that make a heuristic move worse
+// than good, or a blunder
+const INACCURACY: i32 = 50;
+const BLUNDER: i32 = 200;
+
+// Extra walls a slower proven win may take and still be good
+const SLOWER_WIN: usize = 2;
+
+// What a wall leads to, from the walls' point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Win(usize),     // Proven trap in this many walls, this one included
+    Heuristic(i32), // Evaluation::score after the cat's reply
+    Escape,         // The cat reaches the edge in reply
+}
+
+impl Value {
+    // Evaluates `wall` on `board` with a search of `budget`
+    fn of(board: &Board, wall: Position, budget: SearchBudget) -> Result<Value, MoveError> {
+        let mut next = board.clone();
+        if !next.move_player(wall) {
+            return Err(MoveError::Illegal(wall));
+        }
+        if next.move_cat().is_none() {
+            return Ok(Value::Win(1));
+        }
+        if next.has_cat_escaped() {
+            return Ok(Value::Escape);
+        }
+        let result = AutoPlayer::with_budget(budget).search(&next, budget, &AtomicBool::new(false), &mut |_| {});
+        Ok(match result.solution {
+            Some(plan) => Value::Win(plan.len() + 1),
+            None => Value::Heuristic(Evaluation::of(&next).score()),
+        })
+    }
+
+    fn rank(&self) -> (u8, Reverse<usize>, i32) {
+        match *self {
+            Value::Win(n) => (2, Reverse(n), 0),
+            Value::Heuristic(score) => (1, Reverse(0), score),
+            Value::Escape => (0, Reverse(0), 0),
+        }
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Win(n) => write!(f, "win in {}", n),
+            Value::Heuristic(score) => write!(f, "score {}", score),
+            Value::Escape => write!(f, "cat escapes"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl Label {
+    // How `played` compares with the best value the solver found
+    fn of(played: Value, best: Value) -> Label {
+        if played >= best {
+            return Label::Best;
+        }
+        match (played, best) {
+            (Value::Win(n), Value::Win(m)) if n <= m + SLOWER_WIN => Label::Good,
+            (Value::Win(_), Value::Win(_)) => Label::Inaccuracy,
+            (Value::Heuristic(played), Value::Heuristic(best)) if best - played < INACCURACY => Label::Good,
+            (Value::Heuristic(played), Value::Heuristic(best)) if best - played < BLUNDER => Label::Inaccuracy,
+            _ => Label::Blunder, // A win thrown away, the cat let out, or a large drop
+        }
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Label::Best => "best",
+            Label::Good => "good",
+            Label::Inaccuracy => "inaccuracy",
+            Label::Blunder => "blunder",
+        };
+        f.pad(s)
+    }
+}
+
+// One analysed wall
+#[derive(Debug, Clone, Copy)]
+pub struct Annotation {
+    pub wall: Position,
+    pub cat: Option<Position>, // The cat's actual reply
+    pub label: Label,
+    pub played: Value,
+    pub best: Option<(Position, Value)>, // The solver's wall, when it is better than the played one
+    pub best_value: Value,               // Best value available before the wall
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub annotations: Vec<Annotation>,
+    pub status: GameStatus,
+    // For a lost game, the last ply (from 0) before whose wall the walls
+    // still had a proven win, if they ever had one
+    pub lost_at: Option<usize>,
+}
+
+// Analyses every wall of `record`, searching each position with `budget`.
+// Stops at the first ply that does not replay legally.
+pub fn analyze(record: &GameRecord, budget: SearchBudget) -> Result<Analysis, String> {
+    let mut game = Game::new(record.initial.clone());
+    let mut annotations = Vec::new();
+
+    for (i, ply) in record.plies.iter().enumerate() {
+        let board = game.board.clone();
+        let result = AutoPlayer::with_budget(budget).search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+        let played = Value::of(&board, ply.wall, budget).map_err(|e| format!("Ply {}: {}", i + 1, e))?;
+        let best = match result.best_move() {
+            Some(wall) if wall != ply.wall => Value::of(&board, wall, budget).ok().map(|value| (wall, value)),
+            _ => None,
+        };
+        let best_value = best.map_or(played, |(_, value)| value.max(played));
+        annotations.push(Annotation {
+            wall: ply.wall,
+            cat: ply.cat,
+            label: Label::of(played, best_value),
+            played,
+            best: best.filter(|&(_, value)| value > played),
+            best_value,
+        });
+
+        let status = game.place_wall(ply.wall).map_err(|e| format!("Ply {}: {}", i + 1, e))?;
+        if status != GameStatus::Playing {
+            break;
+        }
+        match ply.cat {
+            Some(p) => game.move_cat_to(p).map_err(|e| format!("Ply {}: {}", i + 1, e))?,
+            None => break,
+        };
+    }
+
+    let status = game.status();
+    let lost_at = if status == GameStatus::Lost {
+        annotations.iter().rposition(|a| matches!(a.best_value, Value::Win(_)))
+    } else {
+        None
+    };
+    Ok(Analysis {
+        annotations,
+        status,
+        lost_at,
+    })
+}
+
+pub fn print_analysis(analysis: &Analysis) {
+    for (i, a) in analysis.annotations.iter().enumerate() {
+        let cat = a.cat.map_or("-".to_string(), |p| p.to_string());
+        print!("{:>3}. {} cat {} {:<10} {}", i + 1, a.wall, cat, a.label, a.played);
+        if let Some((wall, value)) = a.best {
+            print!(" (better: {}, {})", wall, value);
+        }
+        println!();
+    }
+    println!("Result: {}", analysis.status);
+    if analysis.status == GameStatus::Lost {
+        match analysis.lost_at {
+            Some(i) => {
+                let a = &analysis.annotations[i];
+                let (wall, value) = a.best.unwrap_or((a.wall, a.played));
+                println!("The game was lost at ply {}: {} had a {}.", i + 1, wall, value);
+            }
+            None => println!("The solver found no winning line at any point."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cat at (5,5) has two ways out, (5,4) into the open and (5,6),
+    // which leads on only through (5,7): walling (5,4) wins in two
+    const BOARD: &str = "\
+- - - - - - - - - -
+ - - - - - - - - - -
+- - - - - - - - - -
+ - - - - - - - - - -
+- - - - - # # # - -
+ - - - - - C - - - -
+- - - - - # # # - -
+ - - - - - - - - - -
+- - - - - - - - - -
+ - - - - - - - - - -
+- - - - - - - - - -
+";
+
+    fn analyze_plies(plies: &str) -> Result<Analysis, String> {
+        let record =
+            GameRecord::from_reader(&mut format!("{}{}", BOARD, plies).as_bytes()).expect("Record should parse");
+        let budget = SearchBudget {
+            nodes: Some(200),
+            ..SearchBudget::default()
+        };
+        analyze(&record, budget)
+    }
+
+    #[test]
+    fn a_won_game_is_best_throughout() {
+        let won = analyze_plies("5 4 5 6\n5 7 -\n").unwrap();
+        assert_eq!(won.status, GameStatus::Won);
+        let values: Vec<(Label, Value)> = won.annotations.iter().map(|a| (a.label, a.played)).collect();
+        assert_eq!(values, [(Label::Best, Value::Win(2)), (Label::Best, Value::Win(1))]);
+        assert_eq!(won.lost_at, None);
+
+        // Walling further along the cat's way out still wins, more slowly
+        let slower = analyze_plies("4 9 5 6\n").unwrap();
+        let a = &slower.annotations[0];
+        assert_eq!((a.label, a.played), (Label::Good, Value::Win(3)));
+        assert_eq!(a.best, Some((Position::new(5, 4), Value::Win(2))));
+    }
+
+    // Closing the far end first lets the cat out through (5,4), and it runs
+    // west to the edge while the walls go elsewhere
+    #[test]
+    fn a_lost_game_is_lost_where_the_win_was_thrown_away() {
+        let lost = analyze_plies("5 7 5 4\n0 0 5 3\n0 1 5 2\n0 2 5 1\n0 3 5 0\n").unwrap();
+        assert_eq!(lost.status, GameStatus::Lost);
+        assert_eq!(lost.annotations.len(), 5);
+        let first = &lost.annotations[0];
+        assert_eq!(first.label, Label::Blunder);
+        assert!(matches!(first.played, Value::Heuristic(_)));
+        assert_eq!(first.best, Some((Position::new(5, 4), Value::Win(2))));
+        assert_eq!(lost.lost_at, Some(0));
+        // Not blocking the cat's last step lets it out
+        let last = &lost.annotations[4];
+        assert_eq!((last.label, last.played), (Label::Blunder, Value::Escape));
+
+        // A wall on a wall or on the cat is an error, not a position
+        assert!(analyze_plies("4 5 5 6\n").is_err());
+        assert!(analyze_plies("5 5 -\n").is_err());
+    }
+
+    #[test]
+    fn labels_follow_the_drop_from_the_best() {
+        use Value::{Escape, Heuristic, Win};
+        assert_eq!(Label::of(Win(2), Win(2)), Label::Best);
+        assert_eq!(Label::of(Win(4), Win(2)), Label::Good);
+        assert_eq!(Label::of(Win(5), Win(2)), Label::Inaccuracy);
+        assert_eq!(Label::of(Heuristic(100), Win(9)), Label::Blunder);
+        assert_eq!(Label::of(Heuristic(100), Heuristic(100 + INACCURACY - 1)), Label::Good);
+        assert_eq!(
+            Label::of(Heuristic(100), Heuristic(100 + INACCURACY)),
+            Label::Inaccuracy
+        );
+        assert_eq!(Label::of(Heuristic(100), Heuristic(100 + BLUNDER)), Label::Blunder);
+        assert_eq!(Label::of(Escape, Heuristic(-500)), Label::Blunder);
+    }
+}
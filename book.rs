@@ -0,0 +1,242 @@
+// --- Opening book ---
+//
+// On the standard random boards the first few walls decide most games, and
+// AutoPlayer would otherwise work them out from scratch every time. The book
+// is built offline: from each of many seeded starting boards, the first walls
+// of the line against `move_cat` are searched with a much larger budget than
+// a move in play gets, and stored under the canonical hash of the position, so
+// a position and its mirror image share an entry. A mirrored hit plays the
+// mirrored wall, which is as good against a cat that ignores directions but
+// only a strong guess against `move_cat`, whose ties depend on them.
+//
+// BookPlayer consults the book it was given until a position is missing,
+// then searches as AutoPlayer; on the command line that is the book loaded
+// with `--book <file>`. The hash leaves out the board's topology, so only
+// plain hex boards are looked up; `book <file> [boards] [plies] [nodes]
+// [seed]` writes one.
+
+use crate::symmetry;
+use crate::topology::Kind;
+use crate::{AutoPlayer, Board, Player, Position, SearchBudget, N};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 6] = b"TTCBK\x01";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    pub wall: Position,        // On the canonical image of the position
+    pub win_in: Option<usize>, // Walls to trap a move_cat cat, this one included, if proven
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: Vec<(u64, BookMove)>, // Sorted by canonical hash
+}
+
+impl Book {
+    // Searches the first `plies` walls from `boards` seeded boards, starting
+    // at `seed`, with `nodes` nodes each
+    pub fn build(boards: usize, plies: usize, nodes: usize, seed: u64) -> Book {
+        let budget = SearchBudget {
+            nodes: Some(nodes),
+            ..SearchBudget::default()
+        };
+        let mut entries = HashMap::new();
+        for i in 0..boards {
+            let mut board = Board::random(seed.wrapping_add(i as u64));
+            for _ in 0..plies {
+                if board.has_cat_escaped() || board.min_cat_moves() == N {
+                    break;
+                }
+                let (image, symmetry) = symmetry::canonical(&board);
+                let hash = image.zobrist_hash();
+                let book_move = match entries.get(&hash) {
+                    Some(&book_move) => book_move,
+                    None => {
+                        // Searched as the board stands, so that its own line
+                        // against move_cat is exact
+                        let result = AutoPlayer::with_budget(budget).search(
+                            &board,
+                            budget,
+                            &AtomicBool::new(false),
+                            &mut |_| {},
+                        );
+                        let Some(wall) = result.best_move() else {
+                            break;
+                        };
+                        let book_move = BookMove {
+                            wall: symmetry.apply(wall),
+                            win_in: result.solution.map(|plan| plan.len()),
+                        };
+                        entries.insert(hash, book_move);
+                        book_move
+                    }
+                };
+                board.move_player(symmetry.undo(book_move.wall));
+                if board.move_cat().is_none() {
+                    break;
+                }
+            }
+            eprintln!("Board {}/{}: {} positions", i + 1, boards, entries.len());
+        }
+        let mut entries: Vec<(u64, BookMove)> = entries.into_iter().collect();
+        entries.sort_by_key(|&(hash, _)| hash);
+        Book { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // The book's wall for `board`, mapped back from the canonical image
+    pub fn probe(&self, board: &Board) -> Option<(Position, BookMove)> {
+        if board.topology.kind() != Kind::Hex {
+            return None;
+        }
+        let (image, symmetry) = symmetry::canonical(board);
+        let i = self
+            .entries
+            .binary_search_by_key(&image.zobrist_hash(), |&(hash, _)| hash)
+            .ok()?;
+        let book_move = self.entries[i].1;
+        let wall = symmetry.undo(book_move.wall);
+        (!board.cells[wall.idx()] && wall != board.cat_position).then_some((wall, book_move))
+    }
+
+    // Header (magic, entry count) then 10 bytes per entry: the hash, the
+    // wall's index and the proven length (0 if not proven)
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for &(hash, book_move) in &self.entries {
+            out.write_all(&hash.to_le_bytes())?;
+            out.write_all(&[book_move.wall.idx() as u8, book_move.win_in.unwrap_or(0) as u8])?;
+        }
+        out.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Book> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut input = BufReader::new(File::open(path)?);
+        let mut header = [0; 10];
+        input.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("Not an opening book file"));
+        }
+        let count = u32::from_le_bytes(header[6..].try_into().expect("4 bytes")) as usize;
+        let mut entries = Vec::with_capacity(count);
+        let mut entry = [0; 10];
+        for _ in 0..count {
+            input.read_exact(&mut entry)?;
+            let hash = u64::from_le_bytes(entry[..8].try_into().expect("8 bytes"));
+            if entry[8] as usize >= N {
+                return Err(invalid("Book wall off the board"));
+            }
+            let book_move = BookMove {
+                wall: Position::from_idx(entry[8] as usize),
+                win_in: (entry[9] != 0).then_some(entry[9] as usize),
+            };
+            entries.push((hash, book_move));
+        }
+        if !entries.is_sorted_by_key(|&(hash, _)| hash) {
+            return Err(invalid("Book entries out of order"));
+        }
+        Ok(Book { entries })
+    }
+}
+
+// Plays from the book while it knows the position, then like AutoPlayer
+pub struct BookPlayer {
+    book: Arc<Book>,
+    in_book: bool,
+    search: AutoPlayer, // Takes over at the first position missing from the book
+}
+
+impl BookPlayer {
+    pub fn new(book: Arc<Book>, search: AutoPlayer) -> Self {
+        BookPlayer {
+            book,
+            in_book: true,
+            search,
+        }
+    }
+}
+
+impl Player for BookPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        if self.in_book {
+            if let Some((wall, _)) = self.book.probe(board) {
+                return Some(wall);
+            }
+            self.in_book = false;
+        }
+        self.search.get_move(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::legal_walls;
+
+    // A book survives the file, and its walls are legal on the boards it
+    // was built from and on their mirror images
+    #[test]
+    fn book_round_trips_and_maps_mirrors() {
+        let book = Book::build(3, 2, 200, 5);
+        let path = std::env::temp_dir().join(format!("trapthecat-book-{}.bin", std::process::id()));
+        book.save(&path).unwrap();
+        let loaded = Book::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries, book.entries);
+
+        for seed in 5..8 {
+            let board = Board::random(seed);
+            let (wall, _) = book.probe(&board).expect("starting boards are in the book");
+            for symmetry in symmetry::SYMMETRIES.iter() {
+                let image = symmetry.apply_board(&board);
+                assert_eq!(book.probe(&image).map(|(w, _)| w), Some(symmetry.apply(wall)));
+            }
+        }
+    }
+
+    // The book's wall is played even where the search would play another,
+    // and the first position missing from the book goes to the search
+    #[test]
+    fn book_player_leaves_the_book_for_the_search() {
+        let budget = SearchBudget {
+            nodes: Some(200),
+            ..SearchBudget::default()
+        };
+        let mut board = Board::random(5);
+        let searched = AutoPlayer::with_budget(budget).get_move(&board);
+        let wall = legal_walls(&board)
+            .into_iter()
+            .find(|&p| Some(p) != searched)
+            .expect("a random board has more than one empty cell");
+        let (image, symmetry) = symmetry::canonical(&board);
+        let book = Book {
+            entries: vec![(
+                image.zobrist_hash(),
+                BookMove {
+                    wall: symmetry.apply(wall),
+                    win_in: None,
+                },
+            )],
+        };
+        let mut player = BookPlayer::new(Arc::new(book), AutoPlayer::with_budget(budget));
+        assert_eq!(player.get_move(&board), Some(wall));
+
+        board.move_player(wall);
+        board.move_cat().expect("one wall does not trap the cat");
+        let searched = AutoPlayer::with_budget(budget).get_move(&board);
+        assert_eq!(player.get_move(&board), searched);
+        assert!(!player.in_book);
+    }
+}
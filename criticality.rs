@@ -0,0 +1,163 @@
+// --- Criticality heatmap ---
+//
+// For coaching: how much a wall on each cell would hurt the cat. Every empty
+// cell of the cat's region is tried in turn and compared with the board as it
+// is, on the cat's distance to the edge, its number of shortest escape paths
+// and the minimum cut. Articulation points of the region are flagged, since a
+// wall there splits off part of the cat's room.
+
+use crate::field::{DistanceField, Grid};
+use crate::{Board, Position, C, N, R};
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Criticality {
+    pub distance: usize, // Rise in min_cat_moves (N if the wall traps the cat)
+    pub paths: u64,      // Shortest escape paths removed, while the distance stays
+    pub cut: usize,      // Fall in the minimum cut
+    pub articulation: bool,
+}
+
+impl Criticality {
+    // 0 to 9 for the overlay: a trapping wall is 9, otherwise each step of
+    // distance counts 4, each wall off the cut 3, and the share of escape
+    // paths removed up to 2
+    pub fn level(&self, paths_before: u64) -> usize {
+        if self.distance == N {
+            return 9;
+        }
+        let share = if self.distance > 0 {
+            1.0
+        } else {
+            self.paths as f64 / paths_before.max(1) as f64
+        };
+        let level = 4.0 * self.distance as f64 + 3.0 * self.cut as f64 + 2.0 * share;
+        (level.round() as usize).min(9)
+    }
+}
+
+// Criticality of every cell a wall can go on in the cat's region; None
+// elsewhere. Empty if the cat is already trapped or out.
+pub fn criticality_map(board: &Board) -> Grid<Option<Criticality>> {
+    let mut map = Grid::new(None);
+    let distance = board.min_cat_moves();
+    if distance == 0 || distance == N {
+        return map;
+    }
+    let paths = board.num_escape_paths();
+    let cut = board.min_vertex_cut().size;
+    let region = board.cat_region();
+    let articulation = region.articulation_points();
+
+    let mut field = DistanceField::new(board);
+    let mut next = board.clone();
+    for &p in region.cells.iter().filter(|&&p| p != board.cat_position) {
+        field.add_wall(p);
+        next.cells.set(p.idx(), true);
+        let new_distance = field.cat_moves(board.cat_position);
+        let critical = if new_distance == N {
+            Criticality {
+                distance: N,
+                paths,
+                cut,
+                articulation: articulation.contains(&p),
+            }
+        } else {
+            Criticality {
+                distance: new_distance - distance,
+                paths: if new_distance == distance {
+                    paths - next.num_escape_paths()
+                } else {
+                    paths
+                },
+                cut: cut - next.min_vertex_cut().size,
+                articulation: articulation.contains(&p),
+            }
+        };
+        map[p] = Some(critical);
+        field.remove_wall(p);
+        next.cells.set(p.idx(), false);
+    }
+    map
+}
+
+// The board with each cell of the cat's region shown as its level, and '*'
+// after articulation points; cells a wall cannot change show as '-'. This is
+// what the board prints as with `{:#}`.
+pub struct Heatmap<'a> {
+    board: &'a Board,
+    map: Grid<Option<Criticality>>,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(board: &'a Board) -> Self {
+        Heatmap {
+            board,
+            map: criticality_map(board),
+        }
+    }
+}
+
+impl Display for Heatmap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paths = self.board.num_escape_paths();
+        for r in 0..R {
+            if self.board.topology.is_shifted(r) {
+                write!(f, "  ")?; // Offset odd rows by half a cell
+            }
+            for c in 0..C {
+                let p = Position::new(r, c);
+                if c != 0 {
+                    write!(f, " ")?;
+                }
+                match self.map[p] {
+                    Some(critical) if critical.level(paths) > 0 || critical.articulation => {
+                        let flag = if critical.articulation { '*' } else { ' ' };
+                        write!(f, "{}{}", critical.level(paths), flag)?;
+                    }
+                    _ => write!(f, "{} ", self.board.symbol(p))?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cat's pocket, the cells around it and the ring around those, is
+    // walled but for (4, 7), which every way out has to pass
+    #[test]
+    fn a_choke_point_is_rated_highest() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        let pocket = board.neighbors(board.cat_position).to_vec();
+        let choke = Position::new(4, 7);
+        let around: Vec<Position> = pocket.iter().flat_map(|&p| board.neighbors(p).to_vec()).collect();
+        for p in around {
+            if p != board.cat_position && !pocket.contains(&p) && p != choke {
+                board.cells.set(p.idx(), true);
+            }
+        }
+
+        let map = criticality_map(&board);
+        let paths = board.num_escape_paths();
+        let critical = map[choke].expect("The choke point is in the cat's region");
+        assert_eq!(critical.distance, N);
+        assert!(critical.articulation);
+        assert_eq!(critical.level(paths), 9);
+        for p in (0..N).map(Position::from_idx).filter(|&p| p != choke) {
+            if let Some(other) = map[p] {
+                assert!(other.level(paths) < 9 && !other.articulation, "{}", p);
+            }
+        }
+
+        let overlay = format!("{:#}", board);
+        assert_eq!(overlay, Heatmap::new(&board).to_string());
+        assert_eq!(overlay.matches("9*").count(), 1);
+        assert_eq!(overlay.lines().nth(4).unwrap().split_whitespace().nth(7), Some("9*"));
+    }
+}
@@ -0,0 +1,136 @@
+// --- Win-probability estimation ---
+//
+// For positions the exact search cannot prove: many games are played out from
+// the same board between a wall policy and a cat policy, each game seeded
+// differently, and the share the walls win is reported with a 95% confidence
+// interval. Policies are strategy names with an optional noise level (see
+// `strategies::wall_policy`), so that deterministic strategies still give a
+// spread of games. Difficulty tuning reads these numbers.
+
+use crate::game::{play_game, GameStatus};
+use crate::rng::Rng;
+use crate::strategies::{cat_policy, wall_policy, wall_strategy_error, Tables};
+use crate::tournament::wilson_interval;
+use crate::Board;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub games: usize,
+    pub wins: usize, // Won by the walls
+    pub interval: (f64, f64),
+    pub walls_in_wins: usize,
+    pub forfeits: usize,
+}
+
+impl Estimate {
+    pub fn win_probability(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    // Walls placed in an average won game
+    pub fn average_walls(&self) -> Option<f64> {
+        (self.wins > 0).then(|| self.walls_in_wins as f64 / self.wins as f64)
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (low, high) = self.interval;
+        write!(
+            f,
+            "won {}/{} ({:.1}%, 95% CI [{:.1}%, {:.1}%])",
+            self.wins,
+            self.games,
+            100.0 * self.win_probability(),
+            100.0 * low,
+            100.0 * high
+        )?;
+        if let Some(walls) = self.average_walls() {
+            write!(f, ", {:.1} walls per win", walls)?;
+        }
+        if self.forfeits > 0 {
+            write!(f, ", {} forfeits", self.forfeits)?;
+        }
+        Ok(())
+    }
+}
+
+// Plays `n` games from `board`, reproducible from `seed`
+pub fn estimate(
+    board: &Board,
+    wall_policy_spec: &str,
+    cat_policy_spec: &str,
+    n: usize,
+    seed: u64,
+    tables: &Tables,
+) -> Result<Estimate, String> {
+    if wall_policy(wall_policy_spec, 0, tables).is_none() {
+        return Err(wall_strategy_error(wall_policy_spec, tables));
+    }
+    if cat_policy(cat_policy_spec, 0).is_none() {
+        return Err(format!("Unknown cat policy '{}'", cat_policy_spec));
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut estimate = Estimate {
+        games: n,
+        wins: 0,
+        interval: (0.0, 1.0),
+        walls_in_wins: 0,
+        forfeits: 0,
+    };
+    for _ in 0..n {
+        let game_seed = rng.next_u64();
+        let mut walls = wall_policy(wall_policy_spec, game_seed, tables).expect("checked above");
+        let mut cat = cat_policy(cat_policy_spec, game_seed).expect("checked above");
+        let result = play_game(board.clone(), walls.as_mut(), cat.as_mut());
+        if result.forfeit.is_some() {
+            estimate.forfeits += 1;
+        }
+        if result.status == GameStatus::Won {
+            estimate.wins += 1;
+            estimate.walls_in_wins += result.walls();
+        }
+    }
+    estimate.interval = wilson_interval(estimate.wins, estimate.games);
+    Ok(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    // A pocket around (5,5) open at (5,4) and, through (5,6), at (5,7).
+    // Greedy walls lose every game to the default cat, so only noise gives
+    // a probability strictly between 0 and 1.
+    #[test]
+    fn noise_spreads_the_games() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        for (r, c) in [(4, 5), (4, 6), (6, 6), (6, 5), (4, 7), (6, 7)] {
+            board.cells.set(Position::new(r, c).idx(), true);
+        }
+        let tables = Tables::default();
+        let certain = estimate(&board, "greedy", "default", 40, 45, &tables).unwrap();
+        assert_eq!(certain.wins, 0);
+        assert_eq!(certain.average_walls(), None);
+        assert_eq!(certain.interval, wilson_interval(0, 40));
+
+        let noisy = estimate(&board, "greedy", "default:0.5", 40, 45, &tables).unwrap();
+        assert!(0 < noisy.wins && noisy.wins < 40, "{}", noisy);
+        assert_eq!(noisy.interval, wilson_interval(noisy.wins, 40));
+        let (low, high) = noisy.interval;
+        assert!(0.0 < low && low < noisy.win_probability() && noisy.win_probability() < high && high < 1.0);
+        assert!(noisy.average_walls().is_some_and(|walls| walls >= 2.0)); // The pocket takes two
+        assert_eq!(noisy.forfeits, 0);
+        assert_eq!(
+            estimate(&board, "greedy", "default:0.5", 40, 45, &tables).unwrap(),
+            noisy
+        );
+
+        assert!(estimate(&board, "greedy", "sleepy", 1, 0, &tables).is_err());
+        assert!(estimate(&board, "greedy:2", "default", 1, 0, &tables).is_err());
+    }
+}
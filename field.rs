@@ -0,0 +1,378 @@
+// --- Per-cell fields ---
+//
+// The BFS from the edges that `move_cat` relies on, kept whole instead of being
+// thrown away once the cat's layer is reached: the distance of every cell to
+// the edge and the number of shortest paths from it to the edge. Heuristics,
+// renderers and analytics read these grids rather than running their own BFS.
+
+use crate::topology::Topology;
+use crate::{Board, Position, C, N, R};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt::{self, Display};
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+// One value per cell, indexed by Position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: [T; N],
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn new(value: T) -> Self {
+        Grid { cells: [value; N] }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: std::array::from_fn(|i| f(&self.cells[i])),
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Position) -> &T {
+        &self.cells[p.idx()]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, p: Position) -> &mut T {
+        &mut self.cells[p.idx()]
+    }
+}
+
+impl<T: Display> Grid<T> {
+    // Laid out like a board on `topology`
+    pub fn display<'a>(&'a self, topology: &'a Topology) -> GridDisplay<'a, T> {
+        GridDisplay { grid: self, topology }
+    }
+}
+
+// A grid laid out like the board, shifted rows offset, every value padded to
+// the widest
+pub struct GridDisplay<'a, T> {
+    grid: &'a Grid<T>,
+    topology: &'a Topology,
+}
+
+impl<T: Display> Display for GridDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self.grid.cells.iter().map(|v| v.to_string()).collect();
+        let width = values.iter().map(String::len).max().unwrap_or(1);
+        for r in 0..R {
+            if self.topology.is_shifted(r) {
+                write!(f, "{:width$}", "", width = width.div_ceil(2))?; // Offset odd rows
+            }
+            for c in 0..C {
+                if c != 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:>width$}", values[Position::new(r, c).idx()], width = width)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Distances and shortest path counts to the edge, from one BFS
+#[derive(Debug, Clone)]
+pub struct EscapeField {
+    pub distance: Grid<Option<usize>>, // None for walls and cells cut off from the edge
+    pub paths: Grid<u64>,              // Saturating; 0 where distance is None
+    topology: Arc<Topology>,
+}
+
+impl EscapeField {
+    // BFS inwards from every empty edge cell, each starting one path. The cat
+    // does not block anything.
+    pub fn of(board: &Board) -> Self {
+        let mut distance = Grid::new(None);
+        let mut paths = Grid::new(0u64);
+        let mut queue = VecDeque::with_capacity(N);
+        for p in board.topology.exits() {
+            if !board.cells[p.idx()] {
+                distance[p] = Some(0);
+                paths[p] = 1;
+                queue.push_back(p);
+            }
+        }
+        while let Some(p) = queue.pop_front() {
+            let d = distance[p].expect("Queued cells have a distance");
+            for &q in board.neighbors(p) {
+                if board.cells[q.idx()] {
+                    continue;
+                }
+                match distance[q] {
+                    None => {
+                        distance[q] = Some(d + 1);
+                        paths[q] = paths[p];
+                        queue.push_back(q);
+                    }
+                    Some(dq) if dq == d + 1 => paths[q] = paths[q].saturating_add(paths[p]),
+                    Some(_) => {}
+                }
+            }
+        }
+        EscapeField {
+            distance,
+            paths,
+            topology: board.topology.clone(),
+        }
+    }
+
+    // Neighbours of `p` one step closer to the edge, most paths first (ties in
+    // the order the topology lists them)
+    pub fn steps_out(&self, p: Position) -> Vec<Position> {
+        let Some(d) = self.distance[p].filter(|&d| d > 0) else {
+            return Vec::new();
+        };
+        let mut steps: Vec<Position> = self
+            .topology
+            .neighbors(p)
+            .iter()
+            .copied()
+            .filter(|&q| self.distance[q] == Some(d - 1))
+            .collect();
+        steps.sort_by_key(|&q| Reverse(self.paths[q]));
+        steps
+    }
+
+    // Up to `limit` shortest paths from `from` to the edge, each listing the
+    // cells stepped on (so the last one is on the edge), most-travelled first
+    pub fn paths_from(&self, from: Position, limit: usize) -> Vec<Vec<Position>> {
+        let mut found = Vec::new();
+        if self.distance[from].is_some() {
+            let mut path = Vec::new();
+            self.extend_paths(from, &mut path, limit, &mut found);
+        }
+        found
+    }
+
+    fn extend_paths(&self, p: Position, path: &mut Vec<Position>, limit: usize, found: &mut Vec<Vec<Position>>) {
+        if found.len() >= limit {
+            return;
+        }
+        if self.distance[p] == Some(0) {
+            found.push(path.clone());
+            return;
+        }
+        for q in self.steps_out(p) {
+            path.push(q);
+            self.extend_paths(q, path, limit, found);
+            path.pop();
+        }
+    }
+}
+
+// Unreachable cells in DistanceField
+const FAR: u8 = u8::MAX;
+
+// Distances to the edge kept up to date as single walls come and go, instead
+// of a full BFS per change. Placing a wall only raises distances: the cells
+// that lost their last neighbour one step closer to the edge are collected
+// nearest first, then given new distances from the unaffected cells around
+// them. Lifting a wall only lowers distances, which spread out from it.
+//
+// The field keeps its own copy of the walls; the owner mirrors every change it
+// makes to the board. Like EscapeField it ignores the cat.
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    walls: [bool; N],
+    distance: [u8; N],
+    topology: Arc<Topology>,
+}
+
+impl DistanceField {
+    pub fn new(board: &Board) -> Self {
+        let distance = EscapeField::of(board).distance;
+        DistanceField {
+            walls: std::array::from_fn(|i| board.cells[i]),
+            distance: std::array::from_fn(|i| distance[Position::from_idx(i)].map_or(FAR, |d| d as u8)),
+            topology: board.topology.clone(),
+        }
+    }
+
+    pub fn distance(&self, p: Position) -> Option<usize> {
+        let d = self.distance[p.idx()];
+        (d != FAR).then_some(d as usize)
+    }
+
+    // Moves from `cat` to the edge, or N if it is cut off (as min_cat_moves)
+    pub fn cat_moves(&self, cat: Position) -> usize {
+        self.distance(cat).unwrap_or(N)
+    }
+
+    pub fn add_wall(&mut self, p: Position) {
+        if self.walls[p.idx()] {
+            return;
+        }
+        self.walls[p.idx()] = true;
+        if self.distance[p.idx()] == FAR {
+            return;
+        }
+
+        // Cells left without a neighbour one step closer, nearest first
+        let mut affected = vec![p];
+        let mut is_affected = [false; N];
+        is_affected[p.idx()] = true;
+        let mut i = 0;
+        while i < affected.len() {
+            let u = affected[i];
+            i += 1;
+            let next = self.distance[u.idx()] + 1;
+            for q in open_neighbors(&self.topology, &self.walls, u) {
+                if is_affected[q.idx()] || self.distance[q.idx()] != next {
+                    continue;
+                }
+                let supported = open_neighbors(&self.topology, &self.walls, q)
+                    .any(|w| !is_affected[w.idx()] && self.distance[w.idx()].saturating_add(1) == next);
+                if !supported {
+                    is_affected[q.idx()] = true;
+                    affected.push(q);
+                }
+            }
+        }
+
+        // New distances, growing from the unaffected cells around them
+        for &u in &affected {
+            self.distance[u.idx()] = FAR;
+        }
+        let mut queue = BinaryHeap::new();
+        for &u in &affected[1..] {
+            let d = open_neighbors(&self.topology, &self.walls, u)
+                .filter(|w| !is_affected[w.idx()])
+                .map(|w| self.distance[w.idx()].saturating_add(1))
+                .min()
+                .unwrap_or(FAR);
+            if d != FAR {
+                self.distance[u.idx()] = d;
+                queue.push(Reverse((d, u.idx())));
+            }
+        }
+        while let Some(Reverse((d, i))) = queue.pop() {
+            if d > self.distance[i] {
+                continue;
+            }
+            for q in open_neighbors(&self.topology, &self.walls, Position::from_idx(i)) {
+                if d + 1 < self.distance[q.idx()] {
+                    self.distance[q.idx()] = d + 1;
+                    queue.push(Reverse((d + 1, q.idx())));
+                }
+            }
+        }
+    }
+
+    pub fn remove_wall(&mut self, p: Position) {
+        if !self.walls[p.idx()] {
+            return;
+        }
+        self.walls[p.idx()] = false;
+        let d = if self.topology.is_exit(p) {
+            0
+        } else {
+            open_neighbors(&self.topology, &self.walls, p)
+                .map(|w| self.distance[w.idx()].saturating_add(1))
+                .min()
+                .unwrap_or(FAR)
+        };
+        self.distance[p.idx()] = d;
+        if d == FAR {
+            return;
+        }
+
+        let mut queue = VecDeque::from([p]);
+        while let Some(u) = queue.pop_front() {
+            let next = self.distance[u.idx()] + 1;
+            for q in open_neighbors(&self.topology, &self.walls, u) {
+                if next < self.distance[q.idx()] {
+                    self.distance[q.idx()] = next;
+                    queue.push_back(q);
+                }
+            }
+        }
+    }
+}
+
+// Neighbours of `p` that are not walls
+fn open_neighbors<'a>(
+    topology: &'a Topology,
+    walls: &'a [bool; N],
+    p: Position,
+) -> impl Iterator<Item = Position> + 'a {
+    topology.neighbors(p).iter().copied().filter(|q| !walls[q.idx()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // Near the top left corner, with walls at (0, 1) and around (8, 5)
+    #[test]
+    fn fields_on_a_hand_built_board() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(2, 2);
+        board.cells.set(Position::new(0, 1).idx(), true);
+        for &p in Topology::hex().neighbors(Position::new(8, 5)) {
+            board.cells.set(p.idx(), true);
+        }
+
+        let distance = board.edge_distance_field();
+        assert_eq!(distance[Position::new(0, 0)], Some(0));
+        assert_eq!(distance[Position::new(0, 1)], None); // A wall
+        assert_eq!(distance[Position::new(8, 5)], None); // Walled in
+        assert_eq!(distance[Position::new(1, 1)], Some(1));
+        assert_eq!(distance[Position::new(2, 2)], Some(2));
+
+        // (1, 1) reaches (1, 0) and (0, 2); (1, 2) reaches (0, 2) and (0, 3);
+        // (2, 1) reaches (1, 0), (2, 0) and (3, 0); (3, 1) reaches (3, 0)
+        let paths = board.escape_path_counts();
+        assert_eq!(paths[Position::new(1, 1)], 2);
+        assert_eq!(paths[Position::new(1, 2)], 2);
+        assert_eq!(paths[Position::new(2, 1)], 3);
+        assert_eq!(paths[Position::new(3, 1)], 1);
+        assert_eq!(paths[Position::new(2, 2)], 8);
+        assert_eq!(paths[Position::new(8, 5)], 0);
+        assert_eq!(board.num_escape_paths(), 8);
+
+        let escapes = board.shortest_escape_paths(N);
+        assert_eq!(escapes.len(), 8);
+        for path in &escapes {
+            assert_eq!(path.len(), 2);
+            assert!(board.neighbors(board.cat_position).contains(&path[0]));
+            assert!(board.neighbors(path[0]).contains(&path[1]) && board.is_exit(path[1]));
+        }
+        assert_eq!(board.shortest_escape_paths(3).len(), 3);
+    }
+
+    // Random walls placed and lifted must leave the same distances as a BFS
+    #[test]
+    fn distance_field_matches_full_bfs() {
+        let mut rng = Rng::new(42);
+        for seed in 0..50 {
+            let mut board = Board::random(seed);
+            let mut field = DistanceField::new(&board);
+            for _ in 0..200 {
+                let p = Position::from_idx(rng.below(N));
+                if board.cells[p.idx()] {
+                    board.cells.set(p.idx(), false);
+                    field.remove_wall(p);
+                } else {
+                    board.cells.set(p.idx(), true);
+                    field.add_wall(p);
+                }
+                let expected = board.edge_distance_field();
+                for q in (0..N).map(Position::from_idx) {
+                    assert_eq!(field.distance(q), expected[q], "seed {} at {} after {}", seed, q, p);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// --- Game state and record ---
+// Shared by everything that plays a game outside of the `main` loop (the HTTP
+// server, sessions, analysis tools, ...). The rules are the same as in `main`:
+// the player places a wall, then the cat answers, by default with
+// `Board::move_cat`.
+
+use crate::{Board, CatMoveError, CatPlayer, Int, Player, Position, N, R};
+use std::fmt::{self, Display};
+use std::io::{self, BufRead};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameStatus {
+    Playing,
+    Won,  // The cat has no way to the edge
+    Lost, // The cat reached the edge
+}
+
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            GameStatus::Playing => "playing",
+            GameStatus::Won => "won",
+            GameStatus::Lost => "lost",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Side {
+    Walls,
+    Cat,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Walls => write!(f, "walls"),
+            Side::Cat => write!(f, "cat"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MoveError {
+    GameOver(GameStatus),
+    NotYourTurn(Side),
+    Illegal(Position), // Occupied, the cat, or out of bounds
+    Cat(CatMoveError),
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GameOver(status) => write!(f, "Game is already over ({})", status),
+            MoveError::NotYourTurn(side) => write!(f, "It is the {} side's turn", side),
+            MoveError::Illegal(p) => write!(
+                f,
+                "Invalid move: {} (either occupied, the cat, or out of bounds)",
+                p
+            ),
+            MoveError::Cat(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// One player wall and the cat's answer (None if the wall trapped the cat, or
+// while the cat has yet to answer)
+#[derive(Debug, Copy, Clone)]
+pub struct Ply {
+    pub wall: Position,
+    pub cat: Option<Position>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub initial: Board,
+    pub plies: Vec<Ply>,
+}
+
+impl GameRecord {
+    // Reads the text form written by Display. Moves are not checked against
+    // the rules; replay the record through `Game` for that.
+    pub fn from_reader<R: BufRead>(reader: &mut R) -> io::Result<Self> {
+        let initial = Board::from_reader(reader)?;
+        let mut plies = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid ply '{}' on line {}", line.trim(), i + 1 + R as usize),
+                )
+            };
+            let numbers: Vec<Option<Int>> = line.split_whitespace().map(|s| s.parse().ok()).collect();
+            let ply = match numbers.as_slice() {
+                [Some(r), Some(c), Some(cr), Some(cc)] => Ply {
+                    wall: Position::new(*r, *c),
+                    cat: Some(Position::new(*cr, *cc)),
+                },
+                [Some(r), Some(c), None] if line.trim_end().ends_with('-') => Ply {
+                    wall: Position::new(*r, *c),
+                    cat: None,
+                },
+                _ => return Err(invalid()),
+            };
+            plies.push(ply);
+        }
+        Ok(GameRecord { initial, plies })
+    }
+}
+
+// Text form: the initial board followed by one "r c r c" line per ply, with
+// "-" in place of the cat's position when the cat was trapped
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.initial)?;
+        for ply in &self.plies {
+            write!(f, "{} {} ", ply.wall.r, ply.wall.c)?;
+            match ply.cat {
+                Some(p) => writeln!(f, "{} {}", p.r, p.c)?,
+                None => writeln!(f, "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub board: Board,
+    pub record: GameRecord,
+    pub turn: Side,
+}
+
+impl Game {
+    pub fn new(board: Board) -> Self {
+        Game {
+            record: GameRecord {
+                initial: board.clone(),
+                plies: Vec::new(),
+            },
+            board,
+            turn: Side::Walls,
+        }
+    }
+
+    pub fn status(&self) -> GameStatus {
+        if self.board.has_cat_escaped() {
+            GameStatus::Lost
+        } else if self.board.min_cat_moves() == N {
+            GameStatus::Won
+        } else {
+            GameStatus::Playing
+        }
+    }
+
+    fn check_turn(&self, side: Side) -> Result<(), MoveError> {
+        let status = self.status();
+        if status != GameStatus::Playing {
+            return Err(MoveError::GameOver(status));
+        }
+        if self.turn != side {
+            return Err(MoveError::NotYourTurn(self.turn));
+        }
+        Ok(())
+    }
+
+    // Places a wall, leaving the cat to answer with `move_cat_to`
+    pub fn place_wall(&mut self, p: Position) -> Result<GameStatus, MoveError> {
+        self.check_turn(Side::Walls)?;
+        if !self.board.move_player(p) {
+            return Err(MoveError::Illegal(p));
+        }
+        self.record.plies.push(Ply { wall: p, cat: None });
+        self.turn = Side::Cat;
+        Ok(self.status())
+    }
+
+    // Moves the cat to an adjacent empty cell
+    pub fn move_cat_to(&mut self, p: Position) -> Result<GameStatus, MoveError> {
+        self.check_turn(Side::Cat)?;
+        self.board.move_cat_to(p).map_err(MoveError::Cat)?;
+        if let Some(ply) = self.record.plies.last_mut() {
+            ply.cat = Some(p);
+        }
+        self.turn = Side::Walls;
+        Ok(self.status())
+    }
+
+    // Places a wall and lets the cat answer. Returns the cat's new position, or
+    // None if the cat is trapped.
+    #[cfg(feature = "server")]
+    pub fn play_wall(&mut self, p: Position) -> Result<Option<Position>, MoveError> {
+        if self.place_wall(p)? != GameStatus::Playing {
+            return Ok(None);
+        }
+        let cat = self.board.best_cat_move();
+        if let Some(target) = cat {
+            self.move_cat_to(target)?;
+        }
+        Ok(cat)
+    }
+}
+
+// Outcome of a game between two automated players
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub status: GameStatus,
+    pub forfeit: Option<Side>, // Side that lost by making an illegal move
+    pub record: GameRecord,
+}
+
+impl GameResult {
+    pub fn walls(&self) -> usize {
+        self.record.plies.len()
+    }
+}
+
+// Plays a game to the end. A side that makes an illegal move forfeits.
+pub fn play_game(board: Board, walls: &mut dyn Player, cat: &mut dyn CatPlayer) -> GameResult {
+    let mut game = Game::new(board);
+    let forfeit = loop {
+        if game.status() != GameStatus::Playing {
+            break None;
+        }
+        let legal = match game.turn {
+            Side::Walls => match walls.get_move(&game.board) {
+                Some(p) => game.place_wall(p).is_ok(),
+                None => false,
+            },
+            Side::Cat => match cat.get_move(&game.board) {
+                Some(p) => game.move_cat_to(p).is_ok(),
+                None => false,
+            },
+        };
+        if !legal {
+            break Some(game.turn);
+        }
+    };
+    let status = match forfeit {
+        Some(Side::Walls) => GameStatus::Lost,
+        Some(Side::Cat) => GameStatus::Won,
+        None => game.status(),
+    };
+    GameResult {
+        status,
+        forfeit,
+        record: game.record,
+    }
+}
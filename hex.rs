@@ -0,0 +1,264 @@
+// --- Hex coordinates ---
+//
+// Positions are stored and shown in offset coordinates, with odd rows shifted
+// half a cell to the right. That stagger makes geometry awkward, so this is
+// the one place that knows about it: axial coordinates (q, r) keep r as the row
+// and slant q so that each direction is a fixed offset, and cube coordinates
+// (x, y, z) with x + y + z = 0 make distances, lines and rotations symmetric.
+// Position::step goes through Axial, as do the symmetries and the tablebase.
+
+use crate::{Direction, Int, Position};
+use std::ops::{Add, Sub};
+
+// Axial offset of each direction, in Direction::ALL order
+const DIRECTIONS: [Axial; 6] = [
+    Axial { q: -1, r: 0 },
+    Axial { q: 0, r: -1 },
+    Axial { q: 1, r: -1 },
+    Axial { q: 1, r: 0 },
+    Axial { q: 0, r: 1 },
+    Axial { q: -1, r: 1 },
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Axial {
+    pub fn new(q: i32, r: i32) -> Self {
+        Axial { q, r }
+    }
+
+    // The offset cell, possibly off the board (but near enough for its
+    // coordinates to fit in a Position)
+    pub fn to_position(self) -> Position {
+        Position::new(self.r as Int, (self.q + (self.r - (self.r & 1)) / 2) as Int)
+    }
+
+    pub fn cube(self) -> Cube {
+        Cube {
+            x: self.q,
+            y: -self.q - self.r,
+            z: self.r,
+        }
+    }
+
+    pub fn step(self, dir: Direction) -> Axial {
+        self + DIRECTIONS[dir as usize]
+    }
+
+    // Moves between the two cells on an open board
+    pub fn distance(self, other: Axial) -> usize {
+        let d = (self - other).cube();
+        d.x.unsigned_abs().max(d.y.unsigned_abs()).max(d.z.unsigned_abs()) as usize
+    }
+
+    // The cells at `radius` from this one, starting west and going clockwise,
+    // so the ring of radius 1 is in Direction::ALL order
+    pub fn ring(self, radius: usize) -> Vec<Axial> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut cell = self + Axial::new(-(radius as i32), 0);
+        let mut ring = Vec::with_capacity(6 * radius);
+        for dir in 0..6 {
+            for _ in 0..radius {
+                ring.push(cell);
+                cell = cell + DIRECTIONS[(dir + 2) % 6];
+            }
+        }
+        ring
+    }
+
+    // The cells a straight line from this one to `other` crosses, both ends
+    // included
+    pub fn line_to(self, other: Axial) -> Vec<Axial> {
+        let n = self.distance(other);
+        let (a, b) = (self.cube(), other.cube());
+        let lerp = |from: i32, to: i32, t: f64| from as f64 + (to - from) as f64 * t;
+        (0..=n)
+            .map(|i| {
+                let t = if n == 0 { 0.0 } else { i as f64 / n as f64 };
+                // Nudged off the cell boundaries so that ties round the same way
+                let x = lerp(a.x, b.x, t) + 1e-6;
+                let y = lerp(a.y, b.y, t) + 2e-6;
+                let z = lerp(a.z, b.z, t) - 3e-6;
+                Cube::round(x, y, z).axial()
+            })
+            .collect()
+    }
+
+    // One of the 12 symmetries of the hex grid about the origin: `t % 6`
+    // turns of 60 degrees clockwise, after a reflection when `t >= 6`
+    pub fn transform(self, t: usize) -> Axial {
+        let Cube { mut x, mut y, mut z } = self.cube();
+        if t >= 6 {
+            std::mem::swap(&mut y, &mut z);
+        }
+        for _ in 0..t % 6 {
+            (x, y, z) = (-z, -x, -y);
+        }
+        Axial::new(x, z)
+    }
+
+    // Turned `turns` times 60 degrees clockwise about `center`
+    pub fn rotate(self, center: Axial, turns: usize) -> Axial {
+        center + (self - center).transform(turns % 6)
+    }
+}
+
+impl Cube {
+    // The cube cell containing a fractional point
+    pub fn round(x: f64, y: f64, z: f64) -> Cube {
+        let (mut rx, mut ry, mut rz) = (x.round(), y.round(), z.round());
+        let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+        // Fix the coordinate that rounded the furthest, keeping the sum 0
+        if dx > dy && dx > dz {
+            rx = -ry - rz;
+        } else if dy > dz {
+            ry = -rx - rz;
+        } else {
+            rz = -rx - ry;
+        }
+        Cube {
+            x: rx as i32,
+            y: ry as i32,
+            z: rz as i32,
+        }
+    }
+
+    pub fn axial(self) -> Axial {
+        Axial::new(self.x, self.z)
+    }
+}
+
+impl From<Position> for Axial {
+    fn from(p: Position) -> Axial {
+        let r = p.r as i32;
+        Axial::new(p.c as i32 - (r - (r & 1)) / 2, r)
+    }
+}
+
+impl Add for Axial {
+    type Output = Axial;
+
+    fn add(self, other: Axial) -> Axial {
+        Axial::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Axial {
+    type Output = Axial;
+
+    fn sub(self, other: Axial) -> Axial {
+        Axial::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+// Reads a cell typed in axial ("a q r") or cube ("x y z") coordinates, or
+// returns None if the words are in neither form, leaving offset "r c" to the
+// caller
+pub fn parse_cell(words: &[&str]) -> Option<Result<Position, String>> {
+    match words {
+        ["a" | "axial", q, r] => Some(parse_axial(q, r)),
+        [x, y, z] => Some(parse_cube(x, y, z)),
+        _ => None,
+    }
+}
+
+fn parse_axial(q: &str, r: &str) -> Result<Position, String> {
+    Ok(typed_cell(Axial::new(coordinate(q)?, coordinate(r)?)))
+}
+
+fn parse_cube(x: &str, y: &str, z: &str) -> Result<Position, String> {
+    let cube = Cube {
+        x: coordinate(x)?,
+        y: coordinate(y)?,
+        z: coordinate(z)?,
+    };
+    if cube.x + cube.y + cube.z != 0 {
+        return Err("Cube coordinates must add up to 0".to_string());
+    }
+    Ok(typed_cell(cube.axial()))
+}
+
+// Typed coordinates can be anything, so those too far off the board to fit in
+// a Position give (-1, -1), which is off the board as well
+fn typed_cell(a: Axial) -> Position {
+    let p = a.to_position();
+    if Axial::from(p) == a {
+        p
+    } else {
+        Position::new(-1, -1)
+    }
+}
+
+fn coordinate(s: &str) -> Result<i32, String> {
+    s.parse().map_err(|_| format!("Invalid coordinate '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::Topology;
+    use crate::{C, N, R};
+
+    #[test]
+    fn axial_matches_the_offset_grid() {
+        for p in (0..N).map(Position::from_idx) {
+            let a = Axial::from(p);
+            assert_eq!(a.to_position(), p);
+            assert_eq!(a.cube().axial(), a);
+            let ring: Vec<Position> = a.ring(1).into_iter().map(Axial::to_position).collect();
+            let steps: Vec<Position> = Direction::ALL.iter().map(|&d| p.step(d)).collect();
+            assert_eq!(ring, steps);
+            // The stagger rule written out on offset coordinates
+            let shift = (p.r + 1) % 2;
+            assert_eq!(p.step(Direction::NorthWest), Position::new(p.r - 1, p.c - shift));
+            assert_eq!(p.step(Direction::NorthEast), Position::new(p.r - 1, p.c - shift + 1));
+            assert_eq!(p.step(Direction::SouthEast), Position::new(p.r + 1, p.c - shift + 1));
+            assert_eq!(p.step(Direction::SouthWest), Position::new(p.r + 1, p.c - shift));
+            for &q in Topology::hex().neighbors(p) {
+                assert_eq!(a.distance(Axial::from(q)), 1);
+            }
+        }
+        let center = Position::new(5, 5);
+        assert_eq!(parse_cell(&["a", "3", "5"]), Some(Ok(center)));
+        assert_eq!(parse_cell(&["3", "-8", "5"]), Some(Ok(center)));
+        assert!(matches!(parse_cell(&["1", "1", "1"]), Some(Err(_))));
+        assert_eq!(parse_cell(&["5", "5"]), None);
+        // Across the board and down its side
+        let corner = Axial::from(Position::new(0, 0));
+        assert_eq!(corner.distance(Axial::from(Position::new(0, C - 1))), C as usize - 1);
+        assert_eq!(corner.distance(Axial::from(Position::new(R - 1, 0))), R as usize - 1);
+    }
+
+    #[test]
+    fn lines_rings_and_rotations() {
+        let center = Axial::from(Position::new(5, 5));
+        for radius in 0..4 {
+            let ring = center.ring(radius);
+            assert_eq!(ring.len(), (6 * radius).max(1));
+            assert!(ring.iter().all(|&a| a.distance(center) == radius));
+            for &a in &ring {
+                let line = center.line_to(a);
+                assert_eq!(line.len(), radius + 1);
+                assert_eq!((line[0], line[radius]), (center, a));
+                assert!(line.windows(2).all(|w| w[0].distance(w[1]) == 1));
+                assert_eq!(a.rotate(center, 1).distance(center), radius);
+                assert_eq!(a.rotate(center, 6), a);
+            }
+        }
+        let east = center.step(Direction::East);
+        assert_eq!(east.rotate(center, 1), center.step(Direction::SouthEast));
+    }
+}
@@ -0,0 +1,173 @@
+// --- Move hints ---
+//
+// Ranks the walls available to the player for tutorials and interactive play.
+// Every wall is scored on the position after it and the cat's `move_cat`
+// reply; the best few then get a short AutoPlayer search, so that a wall that
+// provably wins is ranked by how quickly it does.
+
+use crate::alphabeta::Evaluation;
+use crate::strategies::legal_walls;
+use crate::{AutoPlayer, Board, Position, SearchBudget, N};
+use std::cmp::Reverse;
+use std::fmt::{self, Display};
+use std::sync::atomic::AtomicBool;
+
+// Nodes searched per candidate to look for a proven win
+const PROOF_NODES: usize = 500;
+
+// Hints shown by the `hint` command without a count
+pub const DEFAULT_HINTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub wall: Position,
+    pub win_in: Option<usize>, // Walls to trap a move_cat cat, this one included, if proven
+    pub score: i32,            // Evaluation::score after the cat's reply, higher is better
+    pub distance: usize,       // min_cat_moves after the cat's reply (N if trapped)
+    pub cut: usize,            // Walls still needed to cut the cat off after its reply
+}
+
+impl Hint {
+    // Evaluates `wall` without a proof search, or None if it cannot be placed
+    fn evaluate(board: &Board, wall: Position) -> Option<Hint> {
+        let mut next = board.clone();
+        if !next.move_player(wall) {
+            return None;
+        }
+        let trapped = next.move_cat().is_none();
+        let evaluation = Evaluation::of(&next);
+        Some(Hint {
+            wall,
+            win_in: trapped.then_some(1),
+            score: evaluation.score(),
+            distance: evaluation.distance,
+            cut: evaluation.cut,
+        })
+    }
+
+    // Looks for a trap after the wall and the cat's reply
+    fn prove(&mut self, board: &Board) {
+        if self.win_in.is_some() || self.distance == 0 {
+            return;
+        }
+        let mut next = board.clone();
+        next.move_player(self.wall);
+        next.move_cat();
+        let budget = SearchBudget {
+            nodes: Some(PROOF_NODES),
+            ..SearchBudget::default()
+        };
+        let result = AutoPlayer::with_budget(budget).search(&next, budget, &AtomicBool::new(false), &mut |_| {});
+        self.win_in = result.solution.map(|plan| plan.len() + 1);
+    }
+
+    // Proven wins first, quickest first, then by score
+    fn rank(&self) -> (Reverse<usize>, i32) {
+        (Reverse(self.win_in.unwrap_or(N)), self.score)
+    }
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.wall)?;
+        match self.win_in {
+            Some(n) => write!(f, "proven win in {}", n)?,
+            None if self.distance == 0 => write!(f, "the cat escapes")?,
+            None => write!(f, "score {}", self.score)?,
+        }
+        if self.distance == N {
+            write!(f, ", the cat is trapped")
+        } else {
+            write!(f, ", cat distance {}, cut {}", self.distance, self.cut)
+        }
+    }
+}
+
+// The `k` best walls on `board`, best first
+pub fn hints(board: &Board, k: usize) -> Vec<Hint> {
+    let mut candidates: Vec<Hint> = legal_walls(board)
+        .into_iter()
+        .filter_map(|p| Hint::evaluate(board, p))
+        .collect();
+    candidates.sort_by_key(|hint| Reverse(hint.rank()));
+    candidates.truncate(k);
+    for hint in &mut candidates {
+        hint.prove(board);
+    }
+    candidates.sort_by_key(|hint| Reverse(hint.rank()));
+    candidates
+}
+
+// How good a particular wall is, on the same terms as `hints`
+pub fn rate_wall(board: &Board, wall: Position) -> Option<Hint> {
+    let mut hint = Hint::evaluate(board, wall)?;
+    hint.prove(board);
+    Some(hint)
+}
+
+// Parses "hint" or "hint <k>", returning how many hints to show
+pub fn parse_hint_command(line: &str) -> Option<usize> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts.as_slice() {
+        ["hint"] => Some(DEFAULT_HINTS),
+        ["hint", k] => k.parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn print_hints(board: &Board, k: usize) {
+    let hints = hints(board, k);
+    if hints.is_empty() {
+        println!("No wall can be placed.");
+    }
+    for (i, hint) in hints.iter().enumerate() {
+        println!("{}. {}", i + 1, hint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cat at (5,5) has two ways out, (5,4) into the open and (5,6),
+    // which leads on only through (5,7). Walling (5,4) forces a win in two,
+    // which the proof search has to find; walls further along move_cat's
+    // way out through (5,6) win more slowly, and (5,7) not provably.
+    #[test]
+    fn hints_rank_a_proven_win_first() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        for (r, c) in [(4, 5), (4, 6), (6, 6), (6, 5), (4, 7), (6, 7)] {
+            board.cells.set(Position::new(r, c).idx(), true);
+        }
+        let hints = hints(&board, 5);
+        assert_eq!(hints.len(), 5);
+        assert_eq!(hints[0].wall, Position::new(5, 4));
+        assert_eq!(hints[0].win_in, Some(2));
+        let proven: Vec<Option<usize>> = hints.iter().map(|hint| hint.win_in).collect();
+        assert_eq!(proven, [Some(2), Some(3), Some(3), None, None]);
+        assert!(hints.windows(2).all(|pair| pair[0].rank() >= pair[1].rank()));
+        for hint in &hints {
+            // The numbers describe the position after the cat's reply
+            let mut next = board.clone();
+            next.move_player(hint.wall);
+            next.move_cat();
+            assert_eq!(hint.distance, next.min_cat_moves(), "{}", hint);
+            assert_eq!(hint.cut, next.min_vertex_cut().size, "{}", hint);
+        }
+
+        // Closing the far end first lets the cat out into the open
+        let rated = rate_wall(&board, Position::new(5, 7)).unwrap();
+        assert_eq!(rated.win_in, None);
+        assert!(rated.score < hints[0].score);
+        assert!(rate_wall(&board, Position::new(4, 5)).is_none());
+    }
+
+    #[test]
+    fn hint_commands() {
+        assert_eq!(parse_hint_command("hint"), Some(DEFAULT_HINTS));
+        assert_eq!(parse_hint_command(" hint 5 "), Some(5));
+        assert_eq!(parse_hint_command("hint many"), None);
+        assert_eq!(parse_hint_command("hints"), None);
+    }
+}
@@ -0,0 +1,97 @@
+// --- Lines of play ---
+//
+// The moves of a winning line in play order: the front is the move to make
+// now, the back the one that finishes the game. Moves are only ever taken from
+// the front, so a line built backwards from its last move has to be built with
+// `push_first`. This is the one place that decides which end is which; every
+// player that caches a line keeps it in a `Line` rather than a bare deque.
+
+use std::collections::{vec_deque, VecDeque};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line<T> {
+    moves: VecDeque<T>,
+}
+
+impl<T> Default for Line<T> {
+    fn default() -> Self {
+        Line { moves: VecDeque::new() }
+    }
+}
+
+impl<T> Line<T> {
+    pub fn new() -> Self {
+        Line::default()
+    }
+
+    // Adds a move after all the others
+    pub fn push(&mut self, mv: T) {
+        self.moves.push_back(mv);
+    }
+
+    // Adds a move before all the others, for building a line from its end
+    pub fn push_first(&mut self, mv: T) {
+        self.moves.push_front(mv);
+    }
+
+    // The move to play now, without taking it
+    pub fn next(&self) -> Option<&T> {
+        self.moves.front()
+    }
+
+    // Takes the move to play now
+    pub fn pop_next(&mut self) -> Option<T> {
+        self.moves.pop_front()
+    }
+
+    // Adds the moves of `rest` after all the others
+    pub fn append(&mut self, mut rest: Line<T>) {
+        self.moves.append(&mut rest.moves);
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+
+    // The moves in play order
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.moves.iter()
+    }
+}
+
+impl<T> FromIterator<T> for Line<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(moves: I) -> Self {
+        Line {
+            moves: moves.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_are_taken_in_play_order() {
+        let mut line = Line::new();
+        line.push(2);
+        line.push(3);
+        line.push_first(1);
+        let mut rest: Line<_> = [4, 5].into_iter().collect();
+        rest.push_first(4);
+        line.append(rest);
+        assert_eq!(line.next(), Some(&1));
+        assert_eq!(line.len(), 6);
+        let moves: Vec<_> = std::iter::from_fn(|| line.pop_next()).collect();
+        assert_eq!(moves, [1, 2, 3, 4, 4, 5]);
+        assert!(line.is_empty());
+    }
+}
@@ -1,13 +1,53 @@
 use bitvec::prelude::*;
 use once_cell::sync::Lazy;
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
 use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, StdinLock, Write};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
 use std::path::Path;
-use std::rc::Rc; // Use Rc for single-threaded shared ownership in AutoPlayer search state
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{env, process};
 
+mod alphabeta;
+mod analysis;
+mod book;
+mod criticality;
+mod estimate;
+mod field;
+mod game;
+mod hex;
+mod hints;
+mod line;
+mod mcts;
+mod mincut;
+mod plan;
+mod protocol;
+mod region;
+mod rng;
+#[cfg(feature = "server")]
+mod server;
+mod session;
+mod strategies;
+mod symmetry;
+mod tablebase;
+mod topology;
+mod tournament;
+
+use criticality::Heatmap;
+use field::{DistanceField, EscapeField, Grid};
+use hex::Axial;
+use plan::{Plan, PlanError};
+use region::Region;
+use rng::Rng;
+use tablebase::Tablebase;
+use topology::{Kind, Topology};
+
 // --- Constants and Types ---
 
 type Int = i8; // Using i8 like C++ int_t
@@ -32,52 +72,81 @@ impl Position {
         (self.r * C + self.c) as usize
     }
 
+    fn from_idx(idx: usize) -> Self {
+        Position::new((idx / C as usize) as Int, (idx % C as usize) as Int)
+    }
+
     fn is_valid(&self) -> bool {
         self.r >= 0 && self.r < R && self.c >= 0 && self.c < C
     }
 
-    fn is_edge(&self) -> bool {
-        self.r == 0 || self.r == R - 1 || self.c == 0 || self.c == C - 1
+    // Adjacent position on the hex grid in the given direction (may be off the board)
+    fn step(&self, dir: Direction) -> Position {
+        Axial::from(*self).step(dir).to_position()
     }
+}
 
-    fn neighbors(&self) -> Vec<Position> {
-        let mut ret = Vec::with_capacity(6);
-        let r = self.r;
-        let c = self.c;
-
-        // Hex grid neighbor logic (same as C++)
-        let positions = [
-            Position::new(r, c - 1),
-            Position::new(r - 1, c - ((r + 1) % 2)),
-            Position::new(r - 1, c - ((r + 1) % 2) + 1),
-            Position::new(r, c + 1),
-            Position::new(r + 1, c - ((r + 1) % 2) + 1),
-            Position::new(r + 1, c - ((r + 1) % 2)),
-        ];
+// --- Direction Enum ---
+
+// The six hex directions, in the order the hex grid lists neighbours
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Direction {
+    West,
+    NorthWest,
+    NorthEast,
+    East,
+    SouthEast,
+    SouthWest,
+}
 
-        for p in positions {
-            if p.is_valid() {
-                ret.push(p);
-            }
+impl Direction {
+    const ALL: [Direction; 6] = [
+        Direction::West,
+        Direction::NorthWest,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::SouthWest,
+    ];
+
+    fn short_name(&self) -> &'static str {
+        match self {
+            Direction::West => "w",
+            Direction::NorthWest => "nw",
+            Direction::NorthEast => "ne",
+            Direction::East => "e",
+            Direction::SouthEast => "se",
+            Direction::SouthWest => "sw",
         }
-        ret
     }
 }
 
-// Use once_cell::sync::Lazy for safe static initialization
-static EDGES: Lazy<Vec<Position>> = Lazy::new(|| {
-    let mut ret = Vec::new();
-    for r in 0..R {
-        for c in 0..C {
-            let p = Position::new(r, c);
-            if p.is_edge() {
-                ret.push(p);
-            }
-        }
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        Direction::ALL
+            .into_iter()
+            .find(|d| d.short_name() == s || format!("{:?}", d).to_ascii_lowercase() == s)
+            .ok_or_else(|| format!("Unknown direction '{}' (expected one of w, nw, ne, e, se, sw)", s))
     }
-    ret
+}
+
+// Random keys for Zobrist hashing: one per cell for a wall, one per cell for the cat
+static ZOBRIST: Lazy<Vec<u64>> = Lazy::new(|| {
+    let mut rng = Rng::new(0x5eedca7);
+    (0..2 * N).map(|_| rng.next_u64()).collect()
 });
 
+fn wall_key(p: Position) -> u64 {
+    ZOBRIST[p.idx()]
+}
+
+fn cat_key(p: Position) -> u64 {
+    ZOBRIST[N + p.idx()]
+}
+
 impl Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({}, {})", self.r, self.c)
@@ -91,6 +160,7 @@ impl Display for Position {
 struct Board {
     cells: BitVec<usize, Lsb0>, // Use bitvec crate
     cat_position: Position,
+    topology: Arc<Topology>, // The hex grid unless the board names another
 }
 
 impl Board {
@@ -98,27 +168,46 @@ impl Board {
         Board {
             cells: bitvec![usize, Lsb0; 0; N], // Initialize N bits to 0 (false)
             cat_position: Position::new(0, 0), // Default, will be overwritten
+            topology: Topology::hex(),
         }
     }
 
-    // Helper to create board from a reader (like the C++ istream operator)
+    // Helper to create board from a reader (like the C++ istream operator).
+    // A first line "topology <spec>" plays it on another topology.
     fn from_reader<R: BufRead>(reader: &mut R) -> io::Result<Self> {
         let mut board = Board::new();
         let mut cat_found = false;
         let mut line_num = 0;
 
+        let mut first = String::new();
+        reader.read_line(&mut first)?;
+        let mut pending = match first.trim().strip_prefix("topology ") {
+            Some(spec) => {
+                let topology = Topology::parse(spec).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                board.topology = Arc::new(topology);
+                line_num += 1;
+                None
+            }
+            None => Some(first),
+        };
+
         for r in 0..R {
             line_num += 1;
-            let mut line = String::new();
-            reader.read_line(&mut line)?;
+            let line = match pending.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = String::new();
+                    reader.read_line(&mut line)?;
+                    line
+                }
+            };
             let chars: Vec<char> = line.trim().chars().collect(); // Trim whitespace
 
             // Adjust column index based on row offset for reading input
-            let mut expected_c = 0;
             let mut char_idx = 0;
-             if r % 2 != 0 {
+             if board.topology.is_shifted(r) {
                  // Skip expected offset space if present
-                 if chars.get(0) == Some(&' ') {
+                 if chars.first() == Some(&' ') {
                       char_idx += 1;
                  }
              }
@@ -126,7 +215,7 @@ impl Board {
             for c in 0..C {
                 let p = Position::new(r, c);
                 // Handle space separation
-                 if expected_c > 0 { // skip expected space
+                 if c > 0 { // skip expected space
                       if chars.get(char_idx) == Some(&' ') {
                            char_idx += 1;
                       } else {
@@ -141,7 +230,6 @@ impl Board {
                     )
                 })?;
                  char_idx += 1;
-                 expected_c +=1;
 
 
                 match s {
@@ -185,98 +273,183 @@ impl Board {
         Ok(board)
     }
 
-    // Mark: move_cat
-    // Attempts the same logic as the C++ version, fixing the layer bug
-    // Returns Option<Position> -> Some(new_position) if moved, None if trapped
-    fn move_cat(&mut self) -> Option<Position> {
-        let mut pos_to_num_paths: HashMap<Position, usize> = HashMap::new();
-        let mut layer: HashSet<Position> = HashSet::new();
-
-        // Initialize BFS from edges
-        for &p in EDGES.iter() {
-            if !self.cells[p.idx()] {
-                pos_to_num_paths.insert(p, 1);
-                layer.insert(p);
-            } else {
-                pos_to_num_paths.insert(p, 0); // Wall on edge counts as 0 paths
+    fn from_file(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Board::from_reader(&mut BufReader::new(file))
+    }
+
+    // Single-line notation: the R rows of symbols without spaces, separated by
+    // '/', after the topology's spec and another '/' if it is not the hex grid
+    fn from_notation(notation: &str) -> io::Result<Self> {
+        let mut rows: Vec<String> = notation.trim().split('/').map(str::to_string).collect();
+        if rows.len() == R as usize + 1 {
+            rows[0] = format!("topology {}", rows[0]);
+        } else if rows.len() != R as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected {} rows in notation, found {}", R, rows.len()),
+            ));
+        }
+        Board::from_reader(&mut rows.join("\n").as_bytes())
+    }
+
+    fn to_notation(&self) -> String {
+        let mut rows: Vec<String> = (0..R)
+            .map(|r| {
+                (0..C)
+                    .map(|c| self.symbol(Position::new(r, c)))
+                    .collect()
+            })
+            .collect();
+        if self.topology.kind() != Kind::Hex {
+            rows.insert(0, self.topology.to_string());
+        }
+        rows.join("/")
+    }
+
+    // Symbol used for a cell in the text formats
+    fn symbol(&self, p: Position) -> char {
+        if self.cat_position == p {
+            'C'
+        } else if self.cells[p.idx()] {
+            '#'
+        } else {
+            '-'
+        }
+    }
+
+    // Standard starting board as generated by the web front-end: cat in the
+    // centre and 8 random wall attempts (attempts landing on the cat are skipped)
+    fn random(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut board = Board::new();
+        board.cat_position = Position::new(R / 2, C / 2);
+        for _ in 0..8 {
+            let p = Position::new(rng.below(R as usize) as Int, rng.below(C as usize) as Int);
+            if p != board.cat_position {
+                board.cells.set(p.idx(), true);
             }
         }
+        board
+    }
 
-        // BFS Loop moving inwards
-        while !layer.is_empty() {
-            // Check if cat is reached in the *current* layer
-            if layer.contains(&self.cat_position) {
-                let mut best_num_paths: usize = 0;
-                let mut best_move: Option<Position> = None;
-
-                for p in self.cat_position.neighbors() {
-                    // Potential move 'p' should NOT be in the current layer
-                    // AND must have been reached by the BFS (i.e., in pos_to_num_paths)
-                    // AND must have paths leading to it (> 0)
-                    if !layer.contains(&p) {
-                         if let Some(&num_paths) = pos_to_num_paths.get(&p) {
-                              if num_paths > best_num_paths {
-                                   best_num_paths = num_paths;
-                                   best_move = Some(p);
-                              }
-                         }
-                    }
-                }
+    // Hash of the walls and the cat, updated incrementally with wall_key and cat_key
+    fn zobrist_hash(&self) -> u64 {
+        let mut hash = cat_key(self.cat_position);
+        for i in self.cells.iter_ones() {
+            hash ^= wall_key(Position::from_idx(i));
+        }
+        hash
+    }
 
-                return match best_move {
-                    Some(pos) => {
-                        // This check corresponds to the C++ throw
-                        if best_num_paths == 0 {
-                            // This state should be unreachable if cat was in layer
-                            eprintln!("Warning: Cat found in BFS layer but no valid outward move found.");
-                            None
-                        } else {
-                             self.cat_position = pos; // Mutate board state
-                             Some(pos)
-                        }
+    // Minimum number of cat moves to reach the edge, or N if the cat is trapped
+    // This is a BFS from the cat's position outward
+    fn min_cat_moves(&self) -> usize {
+        let mut visited = [false; N];
+        let mut queue: VecDeque<(Position, usize)> = VecDeque::with_capacity(N); // (Position, distance)
 
-                    }
-                    None => None, // Trapped if no valid neighbor closer to edge exists
-                };
+        visited[self.cat_position.idx()] = true;
+        queue.push_back((self.cat_position, 0));
+
+        while let Some((current_pos, distance)) = queue.pop_front() {
+            if self.is_exit(current_pos) {
+                return distance; // Found shortest path to edge
             }
 
-            // Build next layer
-            let mut next_layer: HashSet<Position> = HashSet::new();
-            let mut layer_updates: HashMap<Position, usize> = HashMap::new(); // Track path updates for next layer
-
-            for &p in &layer {
-                let current_paths = *pos_to_num_paths.get(&p).unwrap_or(&0);
-                if current_paths == 0 { continue; } // Skip nodes with no path in
-
-                for pp in p.neighbors() {
-                    if self.cells[pp.idx()] { continue; } // Skip walls
-
-                    // If pp hasn't been processed AT ALL yet by the BFS
-                    if !pos_to_num_paths.contains_key(&pp) {
-                         // Add to next layer and initialize path count based on current node p
-                         next_layer.insert(pp);
-                         *layer_updates.entry(pp).or_insert(0) += current_paths;
-                    } else if !layer.contains(&pp) {
-                        // If pp was processed in a *previous* layer, do nothing here.
-                        // If pp is being reached from another node 'p' in the *same current layer*, add paths.
-                        // We need to be careful here. Let's refine: Only add paths if pp *will be* in next_layer.
-                         if next_layer.contains(&pp) {
-                            *layer_updates.entry(pp).or_insert(0) += current_paths;
-                         }
-                    }
+            for &neighbor in self.neighbors(current_pos) {
+                // Check not visited AND not a wall on the board
+                if !self.cells[neighbor.idx()] && !visited[neighbor.idx()] {
+                    visited[neighbor.idx()] = true;
+                    queue.push_back((neighbor, distance + 1));
                 }
             }
+        }
 
-            // Apply the accumulated path updates for the next layer
-            for (pos, count_increase) in layer_updates {
-                *pos_to_num_paths.entry(pos).or_insert(0) += count_increase;
-            }
+        N // Cat is trapped, return a large value indicating this
+    }
 
-            layer = next_layer; // Move to the next layer
-        }
+    // Mark: move_cat
+    // Attempts the same logic as the C++ version, fixing the layer bug
+    // Returns Option<Position> -> Some(new_position) if moved, None if trapped
+    fn move_cat(&mut self) -> Option<Position> {
+        let pos = self.best_cat_move()?;
+        self.cat_position = pos; // Mutate board state
+        Some(pos)
+    }
+
+    // The move the cat would make, without making it: the step towards the
+    // edge with the most shortest paths, the first neighbour listed on ties
+    fn best_cat_move(&self) -> Option<Position> {
+        EscapeField::of(self).steps_out(self.cat_position).first().copied()
+    }
+
+    // Number of shortest paths from the cat to the edge, as counted by move_cat
+    // (each empty edge cell starts one path), or 0 if the cat is trapped
+    fn num_escape_paths(&self) -> u64 {
+        self.escape_path_counts()[self.cat_position]
+    }
 
-        // If loop finishes and cat wasn't found, it's trapped
-        None
+    // Distance of every cell to the nearest empty edge cell, None for walls
+    // and cells cut off from the edge
+    fn edge_distance_field(&self) -> Grid<Option<usize>> {
+        EscapeField::of(self).distance
+    }
+
+    // Number of shortest paths from every cell to the edge, as counted by
+    // move_cat (each empty edge cell starts one path); saturates at u64::MAX
+    fn escape_path_counts(&self) -> Grid<u64> {
+        EscapeField::of(self).paths
+    }
+
+    // Up to `limit` shortest paths for the cat to the edge, each listing the
+    // cells it steps on, the path move_cat follows first
+    fn shortest_escape_paths(&self, limit: usize) -> Vec<Vec<Position>> {
+        EscapeField::of(self).paths_from(self.cat_position, limit)
+    }
+
+    // Cells the cat can still reach, its own included
+    fn cat_region(&self) -> Region {
+        Region::of(self)
+    }
+
+    // Whether the cat is cut off from the edge with at most `k` cells to move in
+    fn is_cat_enclosed_within(&self, k: usize) -> bool {
+        self.cat_region().is_enclosed_within(k)
+    }
+
+    // Fewest walls that separate the cat from the edge, with one such set of cells
+    fn min_vertex_cut(&self) -> mincut::VertexCut {
+        mincut::min_vertex_cut(self)
+    }
+
+    // Empty edge cells next to the cat: the cut it must get through in one step
+    fn exits_beside_cat(&self) -> usize {
+        let neighbors = self.neighbors(self.cat_position);
+        neighbors.iter().filter(|&&p| self.is_exit(p) && !self.cells[p.idx()]).count()
+    }
+
+    // Cells next to `p`, in the order the board's topology lists them
+    fn neighbors(&self, p: Position) -> &[Position] {
+        self.topology.neighbors(p)
+    }
+
+    // Whether the cat escapes from `p`: on the edge, unless the topology
+    // marks its own exits
+    fn is_exit(&self, p: Position) -> bool {
+        self.topology.is_exit(p)
+    }
+
+    fn has_cat_escaped(&self) -> bool {
+        self.is_exit(self.cat_position)
+    }
+
+    // The neighbour of `p` in the direction named by `name`, on the grids
+    // that have directions
+    fn step_towards(&self, p: Position, name: &str) -> Result<Position, String> {
+        let dir = name.parse::<Direction>()?;
+        self.topology
+            .step(p, dir)
+            .ok_or_else(|| format!("The {} grid has no directions, enter a cell instead", self.topology))
     }
 
     // move_player remains similar
@@ -288,12 +461,52 @@ impl Board {
             false
         }
     }
+
+    // Moves the cat to p, which must be an empty cell next to it
+    fn move_cat_to(&mut self, p: Position) -> Result<(), CatMoveError> {
+        if !p.is_valid() {
+            return Err(CatMoveError::OffBoard(p));
+        }
+        if !self.neighbors(self.cat_position).contains(&p) {
+            return Err(CatMoveError::NotAdjacent(p));
+        }
+        if self.cells[p.idx()] {
+            return Err(CatMoveError::Wall(p));
+        }
+        self.cat_position = p;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CatMoveError {
+    OffBoard(Position),
+    NotAdjacent(Position),
+    Wall(Position),
+}
+
+impl Display for CatMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatMoveError::OffBoard(p) => write!(f, "The cat cannot leave the board at {}.", p),
+            CatMoveError::NotAdjacent(p) => write!(f, "The cat can only move one step, {} is too far.", p),
+            CatMoveError::Wall(p) => write!(f, "The cat cannot move onto the wall at {}.", p),
+        }
+    }
 }
 
+// `{:#}` overlays the criticality heatmap on the board. A topology other
+// than the hex grid is named on a first line, as Board::from_reader reads it.
 impl Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.topology.kind() != Kind::Hex {
+            writeln!(f, "topology {}", self.topology)?;
+        }
+        if f.alternate() {
+            return write!(f, "{}", Heatmap::new(self));
+        }
         for r in 0..R {
-            if r % 2 != 0 {
+            if self.topology.is_shifted(r) {
                 write!(f, " ")?; // Offset odd rows
             }
             for c in 0..C {
@@ -301,14 +514,7 @@ impl Display for Board {
                 if c != 0 {
                     write!(f, " ")?; // Space between columns
                 }
-                let symbol = if self.cat_position == p {
-                    'C'
-                } else if self.cells[p.idx()] {
-                    '#'
-                } else {
-                    '-'
-                };
-                write!(f, "{}", symbol)?;
+                write!(f, "{}", self.symbol(p))?;
             }
             writeln!(f)?; // Newline after each row
         }
@@ -319,15 +525,18 @@ impl Display for Board {
 // --- Player Trait and Implementations ---
 
 trait Player {
-    // Use &mut self if player needs to maintain state (like AutoPlayer cache)
-    fn get_move(&mut self, board: &Board) -> Position;
+    // Use &mut self if player needs to maintain state (like AutoPlayer cache).
+    // Returns the cell to wall, or None if there is no empty cell left.
+    fn get_move(&mut self, board: &Board) -> Option<Position>;
 }
 
+// A human at the terminal, who can ask for hints or a heatmap and is told how
+// good each wall was
 struct InteractivePlayer;
 
 impl Player for InteractivePlayer {
-    fn get_move(&mut self, _board: &Board) -> Position {
-        println!("Please enter your move (r c):");
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        println!("Please enter your move (r c, axial a q r or cube x y z), 'hint [k]' for suggestions or 'heatmap':");
         loop {
             io::stdout().flush().expect("Failed to flush stdout"); // Ensure prompt is shown
             let mut input = String::new();
@@ -335,12 +544,35 @@ impl Player for InteractivePlayer {
                 .read_line(&mut input)
                 .expect("Failed to read line");
 
-            let parts: Vec<&str> = input.trim().split_whitespace().collect();
-            if parts.len() == 2 {
+            if let Some(k) = hints::parse_hint_command(&input) {
+                hints::print_hints(board, k);
+                continue;
+            }
+            if input.trim() == "heatmap" {
+                print!("{:#}", board);
+                continue;
+            }
+
+            let parts: Vec<&str> = input.split_whitespace().collect();
+            if let Some(cell) = hex::parse_cell(&parts) {
+                match cell {
+                    Ok(pos) if pos.is_valid() => {
+                        if let Some(rating) = hints::rate_wall(board, pos) {
+                            println!("Your wall {}", rating);
+                        }
+                        return Some(pos);
+                    }
+                    Ok(_) => println!("Invalid position: coordinates out of bounds."),
+                    Err(e) => println!("{}", e),
+                }
+            } else if parts.len() == 2 {
                 if let (Ok(r), Ok(c)) = (parts[0].parse::<Int>(), parts[1].parse::<Int>()) {
                     let pos = Position::new(r, c);
                     if pos.is_valid() {
-                         return pos;
+                         if let Some(rating) = hints::rate_wall(board, pos) {
+                              println!("Your wall {}", rating);
+                         }
+                         return Some(pos);
                     } else {
                          println!("Invalid position: coordinates out of bounds.");
                     }
@@ -355,72 +587,145 @@ impl Player for InteractivePlayer {
     }
 }
 
+// --- Cat Player Trait and Implementations ---
+
+trait CatPlayer {
+    // Returns the cell the cat wants to move to, or None if it has no move
+    fn get_move(&mut self, board: &Board) -> Option<Position>;
+}
+
+// The built-in cat: follows Board::move_cat
+struct DefaultCat;
+
+impl CatPlayer for DefaultCat {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        board.best_cat_move()
+    }
+}
+
+// A human cat choosing one of the six hex directions
+struct InteractiveCat;
+
+impl CatPlayer for InteractiveCat {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        println!("Please enter the cat's direction (w, nw, ne, e, se, sw) or its cell (r c, a q r or x y z):");
+        loop {
+            io::stdout().flush().expect("Failed to flush stdout"); // Ensure prompt is shown
+            let mut input = String::new();
+            io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read line");
+
+            let words: Vec<&str> = input.split_whitespace().collect();
+            let cell = match words.as_slice() {
+                [r, c] => match (r.parse::<Int>(), c.parse::<Int>()) {
+                    (Ok(r), Ok(c)) => Some(Ok(Position::new(r, c))),
+                    _ => Some(Err("Invalid input. Please enter two numbers (e.g., '5 4').".to_string())),
+                },
+                words => hex::parse_cell(words),
+            };
+            match cell.unwrap_or_else(|| board.step_towards(board.cat_position, &input)) {
+                Ok(p) => return Some(p),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
+
 // --- AutoPlayer and Search State ---
 
-// State for the A* search used by AutoPlayer
-#[derive(Debug, Clone)]
+// Marks the root in SearchState::parent
+const NO_PARENT: u32 = u32::MAX;
+
+// SearchState::cut_size of a state whose cut has not been computed yet
+const UNKNOWN_CUT: u8 = u8::MAX;
+
+// Node of the best-first search used by AutoPlayer, kept to a few bytes so
+// that millions fit in memory: only the wall that led here and a compact
+// heuristic. The board is rebuilt from the walls along the parent chain.
+#[derive(Debug, Clone, Copy)]
 struct SearchState {
-    board: Board,
-    // Use Option for cached value, None means not calculated yet
-    cached_min_cat_moves: Option<usize>,
-    num_moves: usize, // Player moves made to reach this state
-    previous: Option<Rc<SearchState>>, // Use Rc for shared ownership
-    move_taken: Option<Position>,      // Player move that led to this state
+    hash: u64,         // Zobrist hash of the board after the wall
+    parent: u32,       // Index of the parent among the expanded states, or NO_PARENT
+    wall: u8,          // Cell index of the wall placed here (unused at the root)
+    cat: u8,           // Cell index of the cat when the wall was placed
+    min_cat_moves: u8, // After the wall, before the cat's reply
+    cut_size: u8,      // Minimum vertex cut, breaks ties between equal distances (or UNKNOWN_CUT)
 }
 
 impl SearchState {
-    // Calculate minimum cat moves to edge from this state's board
-    // This is a BFS from the cat's position outward
-    fn calculate_min_cat_moves(&self) -> usize {
-        let mut visited: HashSet<Position> = HashSet::new();
-        let mut queue: VecDeque<(Position, usize)> = VecDeque::new(); // (Position, distance)
+    fn root(board: &Board) -> Self {
+        SearchState {
+            hash: board.zobrist_hash(),
+            parent: NO_PARENT,
+            wall: 0,
+            cat: board.cat_position.idx() as u8,
+            min_cat_moves: board.min_cat_moves() as u8,
+            cut_size: board.min_vertex_cut().size as u8,
+        }
+    }
 
-        visited.insert(self.board.cat_position);
-        queue.push_back((self.board.cat_position, 0));
+    // Child for a wall placed with the cat at `cat`, leaving it `min_cat_moves`
+    // from the edge. Its cut is left for when it comes up in the queue, as most
+    // children never do.
+    fn child(cat: Position, min_cat_moves: usize, hash: u64, parent: usize, wall: Position) -> Self {
+        SearchState {
+            hash,
+            parent: parent as u32,
+            wall: wall.idx() as u8,
+            cat: cat.idx() as u8,
+            min_cat_moves: min_cat_moves as u8,
+            cut_size: UNKNOWN_CUT,
+        }
+    }
 
-        while let Some((current_pos, distance)) = queue.pop_front() {
-            if current_pos.is_edge() {
-                return distance; // Found shortest path to edge
-            }
+    // Heap key; an unknown cut counts as the best possible one, so a state
+    // only comes up with its real cut once nothing else can come first
+    fn rank(&self) -> (u8, Reverse<u8>) {
+        let cut = if self.cut_size == UNKNOWN_CUT { 0 } else { self.cut_size };
+        (self.min_cat_moves, Reverse(cut))
+    }
 
-            for neighbor in current_pos.neighbors() {
-                // Check not visited AND not a wall on the board
-                if !self.board.cells[neighbor.idx()] && visited.insert(neighbor) {
-                    queue.push_back((neighbor, distance + 1));
-                }
-            }
+    // Walls from the root to this state in play order, looked up in `expanded`
+    fn walls(&self, expanded: &[SearchState]) -> VecDeque<Position> {
+        let mut walls = VecDeque::new();
+        let mut state = self;
+        while state.parent != NO_PARENT {
+            walls.push_front(Position::from_idx(state.wall as usize));
+            state = &expanded[state.parent as usize];
         }
-
-        N // Cat is trapped, return a large value indicating this
+        walls
     }
 
-    // Lazily calculate and cache the min_cat_moves
-    fn min_cat_moves(&mut self) -> usize {
-        if self.cached_min_cat_moves.is_none() {
-            self.cached_min_cat_moves = Some(self.calculate_min_cat_moves());
+    // Walls from the root to this state, each with the cat's position after
+    // its reply as the search saw it; `last_reply` answers this state's wall
+    fn line(&self, expanded: &[SearchState], last_reply: Option<Position>) -> Plan {
+        let mut line = Plan::new();
+        let mut reply = last_reply;
+        let mut state = self;
+        while state.parent != NO_PARENT {
+            line.push_first(Position::from_idx(state.wall as usize), reply);
+            reply = Some(Position::from_idx(state.cat as usize));
+            state = &expanded[state.parent as usize];
         }
-        self.cached_min_cat_moves.unwrap()
+        line
     }
 
-    // Priority for the Max-Heap (BinaryHeap): Higher score is better (cat further from edge)
-    fn priority(&mut self) -> usize {
-        self.min_cat_moves()
+    // The board after this state's wall, before the cat's reply
+    fn board(&self, root: &Board, expanded: &[SearchState]) -> Board {
+        let mut board = root.clone();
+        for p in self.walls(expanded) {
+            board.cells.set(p.idx(), true);
+        }
+        board.cat_position = Position::from_idx(self.cat as usize);
+        board
     }
 }
 
-// Implement Ord, PartialOrd, Eq, PartialEq for BinaryHeap (Max-Heap)
-// Note: We compare based on priority, which calls min_cat_moves (mutates cache)
-// This is generally discouraged, but acceptable if priority() is stable after first call.
-// A better approach might be to ensure priority is calculated *before* insertion.
+// Max-heap order: furthest cat first, then fewest walls left to cut it off
 impl Ord for SearchState {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // We need mutable access to calculate priority if not cached.
-        // This is tricky with Ord. Let's assume priority is pre-calculated or
-        // use a wrapper type in the heap.
-        // For simplicity here, assume cached value exists or use default.
-        let self_prio = self.cached_min_cat_moves.unwrap_or(0);
-        let other_prio = other.cached_min_cat_moves.unwrap_or(0);
-        self_prio.cmp(&other_prio) // Higher distance = higher priority
+        self.rank().cmp(&other.rank())
     }
 }
 
@@ -432,8 +737,7 @@ impl PartialOrd for SearchState {
 
 impl PartialEq for SearchState {
     fn eq(&self, other: &Self) -> bool {
-        // Equality might be based on board state, or priority for heap purposes
-        self.cached_min_cat_moves == other.cached_min_cat_moves // Simple equality for heap ordering
+        self.cmp(other) == std::cmp::Ordering::Equal // Equality for heap ordering only
     }
 }
 
@@ -441,111 +745,418 @@ impl Eq for SearchState {}
 
 
 struct AutoPlayer {
-    cached_moves: VecDeque<Position>,
+    plan: Plan, // Rest of the winning line, next wall first
+    expected_cat: Option<Position>, // Predicted cat position at the next call
+    budget: SearchBudget,
+    threads: usize, // Search threads, see AutoPlayer::search
+    tablebase: Option<Arc<Tablebase>>, // Solved small regions the search stops at
 }
 
 impl AutoPlayer {
      fn new() -> Self {
-          AutoPlayer { cached_moves: VecDeque::new() }
+          // Limit search iterations to prevent infinite loops in impossible scenarios
+          AutoPlayer::with_budget(SearchBudget {
+              nodes: Some(50000), // Adjust as needed
+              ..SearchBudget::default()
+          })
+     }
+
+     fn with_budget(budget: SearchBudget) -> Self {
+          AutoPlayer { plan: Plan::new(), expected_cat: None, budget, threads: 1, tablebase: None }
+     }
+
+     // Checks that the rest of the current plan traps `cat` from `board`,
+     // returning the number of walls it takes
+     fn verify_plan(&self, board: &Board, cat: &mut dyn CatPlayer) -> Result<usize, PlanError> {
+          self.plan.verify(board, cat)
      }
 }
 
-impl Player for AutoPlayer {
-    fn get_move(&mut self, board: &Board) -> Position {
-        if let Some(pos) = self.cached_moves.pop_front() { // Use FIFO for cached plan
-            return pos;
-        }
-
-        // A* Search (using BFS distance as heuristic)
-        let mut pq = BinaryHeap::new(); // Max-heap based on Ord implementation
-        let mut initial_state = SearchState {
-            board: board.clone(),
-            cached_min_cat_moves: None,
-            num_moves: 0,
-            previous: None,
-            move_taken: None,
+// Resources a single AutoPlayer search may use; None means unlimited
+#[derive(Debug, Clone, Copy, Default)]
+struct SearchBudget {
+    nodes: Option<usize>,   // Over all search threads
+    time: Option<Duration>, // Wall-clock deadline, measured from the start of the search
+    memory: Option<usize>,  // Approximate bytes held by the queues, expanded states and transposition table
+}
+
+impl SearchBudget {
+    fn is_exhausted(&self, info: &SearchInfo) -> bool {
+        self.nodes.is_some_and(|n| info.nodes >= n)
+            || self.time.is_some_and(|t| info.elapsed >= t)
+            || self.memory.is_some_and(|m| info.memory >= m)
+    }
+}
+
+// Progress report passed to the caller while searching
+#[derive(Debug, Clone, Copy)]
+struct SearchInfo {
+    nodes: usize,
+    depth: usize,              // Deepest player move count popped so far
+    best_move: Option<Position>, // First move of the most promising line so far
+    best_score: usize,         // min_cat_moves at the end of that line
+    elapsed: Duration,
+    memory: usize, // Approximate bytes held by the queues, expanded states and transposition table
+}
+
+// Outcome of a search: the winning line if one was found, else just the
+// first move of the most promising line
+#[derive(Debug, Clone)]
+struct SearchResult {
+    solution: Option<Plan>,
+    info: SearchInfo,
+}
+
+impl SearchResult {
+    fn best_move(&self) -> Option<Position> {
+        match &self.solution {
+            Some(plan) => plan.next().map(|step| step.wall),
+            None => self.info.best_move,
+        }
+    }
+
+    // A proven result traps the cat if it keeps playing like move_cat; a
+    // heuristic one is only the most promising move found within the budget
+    fn is_proven(&self) -> bool {
+        self.solution.is_some()
+    }
+}
+
+// How often (in nodes) progress is reported
+const INFO_INTERVAL: usize = 4096;
+
+// Shards of the transposition table, each behind its own lock, so that
+// threads expanding different positions rarely wait for each other
+const TABLE_SHARDS: usize = 64;
+
+// State shared by the threads of one search
+struct SharedSearch<'a> {
+    budget: SearchBudget,
+    start: Instant,
+    stop: &'a AtomicBool,
+    solved: AtomicBool, // Set by the first thread to find a winning line
+    nodes: AtomicUsize,
+    memory: AtomicUsize, // Bytes allocated for the threads' queues and expanded states
+    // Transposition table of the Zobrist hashes of positions (walls to move)
+    // already expanded by any thread, sharded by hash. Equal boards hold the
+    // same walls, so lines reaching one are equally long and a single
+    // expansion covers them all.
+    expanded: Vec<Mutex<HashSet<u64>>>,
+    table_memory: AtomicUsize, // Bytes allocated for `expanded`
+    tablebase: Option<&'a Tablebase>,
+}
+
+impl SharedSearch<'_> {
+    // The node budget is shared: every thread stops once all of them
+    // together have expanded that many
+    fn should_stop(&self, info: &SearchInfo) -> bool {
+        let nodes = self.nodes.load(AtomicOrdering::Relaxed);
+        self.budget.is_exhausted(&SearchInfo { nodes, ..*info })
+            || self.stop.load(AtomicOrdering::Relaxed)
+            || self.solved.load(AtomicOrdering::Relaxed)
+    }
+
+    fn memory(&self) -> usize {
+        self.memory.load(AtomicOrdering::Relaxed) + self.table_memory.load(AtomicOrdering::Relaxed)
+    }
+
+    // Records a position as expanded, returning false if it already was
+    fn insert_expanded(&self, hash: u64) -> bool {
+        let shard = &self.expanded[hash as usize % TABLE_SHARDS];
+        let mut table = shard.lock().expect("Transposition table poisoned");
+        let capacity = table.capacity();
+        let inserted = table.insert(hash);
+        let grown = table.capacity() - capacity; // Capacities only grow
+        self.table_memory.fetch_add(grown * std::mem::size_of::<u64>(), AtomicOrdering::Relaxed);
+        inserted
+    }
+}
+
+// What one search thread found
+struct PartialResult {
+    solution: Option<Plan>,
+    info: SearchInfo,
+    best_cut: usize, // Cut size behind info.best_move
+}
+
+impl AutoPlayer {
+    // Best-first search for a sequence of walls that traps the cat.
+    // Stops when a solution is found, the budget is exhausted, or `stop` is set.
+    // With several threads the walls after the root are split between them
+    // (root splitting); only the calling thread reports progress.
+    fn search(
+        &self,
+        board: &Board,
+        budget: SearchBudget,
+        stop: &AtomicBool,
+        on_info: &mut dyn FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let shared = SharedSearch {
+            budget,
+            start: Instant::now(),
+            stop,
+            solved: AtomicBool::new(false),
+            nodes: AtomicUsize::new(0),
+            memory: AtomicUsize::new(0),
+            expanded: (0..TABLE_SHARDS).map(|_| Mutex::new(HashSet::new())).collect(),
+            table_memory: AtomicUsize::new(0),
+            tablebase: self.tablebase.as_deref(),
+        };
+        let threads = self.threads.max(1);
+
+        let results = thread::scope(|scope| {
+            let helpers: Vec<_> = (1..threads)
+                .map(|part| {
+                    let shared = &shared;
+                    scope.spawn(move || search_part(board, shared, part, threads, &mut |_| {}))
+                })
+                .collect();
+            let mut results = vec![search_part(board, &shared, 0, threads, on_info)];
+            results.extend(helpers.into_iter().map(|h| h.join().expect("Search thread panicked")));
+            results
+        });
+
+        let mut info = SearchInfo {
+            nodes: shared.nodes.load(AtomicOrdering::Relaxed),
+            depth: results.iter().map(|r| r.info.depth).max().unwrap_or(0),
+            best_move: None,
+            best_score: 0,
+            elapsed: shared.start.elapsed(),
+            memory: shared.memory(),
         };
-        // Pre-calculate priority before inserting into heap
-        initial_state.min_cat_moves();
-        pq.push(initial_state);
-
-        // Limit search depth/iterations to prevent infinite loops in impossible scenarios
-        let mut iterations = 0;
-        let max_iterations = 50000; // Adjust as needed
-
-        while let Some(mut current_state) = pq.pop() {
-            iterations += 1;
-            if iterations > max_iterations {
-                 eprintln!("AutoPlayer search limit reached, giving up and picking arbitrary move.");
-                 // Fallback: find *any* valid move
-                 for r in 0..R { for c in 0..C { let p = Position::new(r, c); if !board.cells[p.idx()] && p != board.cat_position { return p; } } }
-                 return board.cat_position; // Should not happen if board has empty cells
+        let solution = results.iter().filter_map(|r| r.solution.clone()).min_by_key(|plan| plan.len());
+        if let Some(plan) = &solution {
+            info.best_move = plan.next().map(|step| step.wall);
+            info.best_score = N;
+        } else if let Some(best) = results
+            .iter()
+            .filter(|r| r.info.best_move.is_some())
+            .max_by_key(|r| (r.info.best_score, Reverse(r.best_cut)))
+        {
+            info.best_move = best.info.best_move;
+            info.best_score = best.info.best_score;
+        } else {
+            info.best_move = heuristic_move(board);
+        }
+        on_info(&info);
+        SearchResult { solution, info }
+    }
+}
+
+// One thread's share of the search: walls after the root whose index is
+// `part` modulo `parts`, and everything below them
+fn search_part(
+    board: &Board,
+    shared: &SharedSearch,
+    part: usize,
+    parts: usize,
+    on_info: &mut dyn FnMut(&SearchInfo),
+) -> PartialResult {
+    let mut info = SearchInfo {
+        nodes: 0,
+        depth: 0,
+        best_move: None,
+        best_score: 0,
+        elapsed: Duration::ZERO,
+        memory: 0,
+    };
+    let state_bytes = std::mem::size_of::<SearchState>();
+    let mut best_cut = N;
+
+    // A* Search (using BFS distance as heuristic)
+    let mut pq = BinaryHeap::new(); // Max-heap based on Ord implementation
+    let mut expanded: Vec<SearchState> = Vec::new(); // Parents of the states in pq
+    pq.push(SearchState::root(board));
+    let mut held = 0; // Bytes of pq and expanded already added to shared.memory
+
+    while let Some(mut state) = pq.pop() {
+        // Capacities only grow, so the allocation is counted as it is made
+        let allocated = (pq.capacity() + expanded.capacity()) * state_bytes;
+        shared.memory.fetch_add(allocated - held, AtomicOrdering::Relaxed);
+        held = allocated;
+        info.elapsed = shared.start.elapsed();
+        info.memory = shared.memory();
+        if shared.should_stop(&info) {
+            break;
+        }
+        if state.cut_size == UNKNOWN_CUT {
+            state.cut_size = state.board(board, &expanded).min_vertex_cut().size as u8;
+            pq.push(state);
+            continue;
+        }
+        info.nodes = shared.nodes.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+        let walls = state.walls(&expanded);
+        info.depth = info.depth.max(walls.len());
+
+        // Rebuild the board after the wall and simulate the cat's reply. At
+        // the root the walls are to move, so the cat stays put.
+        let mut current = state.board(board, &expanded);
+        let cat_move_result = if walls.is_empty() {
+            Some(current.cat_position)
+        } else {
+            current.move_cat() // Mutates current
+        };
+        let current_priority = current.min_cat_moves();
+
+        // Check if this state is a winning state (cat is trapped), or one
+        // wall away from it: a minimum cut of a single cell traps the cat
+        let mut solution = None;
+        let mut cut_size = 0;
+        if current_priority == N || cat_move_result.is_none() {
+            if !walls.is_empty() {
+                solution = Some(state.line(&expanded, None));
+            }
+        } else {
+            let cut = current.min_vertex_cut();
+            if cut.size == 1 {
+                let mut plan = state.line(&expanded, cat_move_result);
+                plan.push(cut.cells[0], None);
+                solution = Some(plan);
+            } else if let Some(rest) = shared.tablebase.and_then(|table| table.plan(&current)) {
+                let mut plan = state.line(&expanded, cat_move_result);
+                plan.append(rest);
+                solution = Some(plan);
             }
+            cut_size = cut.size;
+        }
+        if let Some(plan) = solution {
+            shared.solved.store(true, AtomicOrdering::Relaxed);
+            info.best_move = plan.next().map(|step| step.wall);
+            info.best_score = N;
+            return PartialResult {
+                solution: Some(plan),
+                info,
+                best_cut: 0,
+            };
+        }
 
+        // Remember the first move of the most promising line seen so far:
+        // furthest cat, then fewest walls left to cut it off
+        if !walls.is_empty()
+            && (info.best_move.is_none()
+                || (current_priority, Reverse(cut_size)) > (info.best_score, Reverse(best_cut)))
+        {
+            info.best_move = walls.front().copied();
+            info.best_score = current_priority;
+            best_cut = cut_size;
+        }
 
-            // Simulate cat's move *after* player's hypothetical move led to current_state
-            // We need a mutable copy to simulate the cat's move
-            let mut board_after_player = current_state.board.clone();
-            let cat_move_result = board_after_player.move_cat(); // Mutates board_after_player
-
-             // Recalculate heuristic *after* cat moves
-             current_state.board = board_after_player; // Update state's board
-             current_state.cached_min_cat_moves = None; // Invalidate cache
-             let current_priority = current_state.min_cat_moves(); // Recalculate
-
-            // Check if this state is a winning state (cat is trapped)
-            if current_priority == N || cat_move_result.is_none() {
-                eprintln!("AutoPlayer found winning sequence after {} iterations!", iterations);
-                let mut path = VecDeque::new();
-                let mut current_rc = Rc::new(current_state); // Wrap in Rc to share for reconstruction
-                while let Some(pos) = current_rc.move_taken {
-                    path.push_front(pos);
-                    if let Some(prev) = &current_rc.previous {
-                        current_rc = prev.clone(); // Move up the chain
-                    } else {
-                        break;
+        if info.nodes.is_multiple_of(INFO_INTERVAL) {
+            on_info(&info);
+        }
+
+        // If cat escaped in simulation, this path is bad (heuristic is 0) - prune?
+        if current.has_cat_escaped() {
+            continue; // Don't explore states where cat has already escaped
+        }
+
+        // Prune lines the walls cannot win: a cat next to the edge steps out
+        // after one more wall, so it escapes when more than one empty edge cell
+        // is beside it. The whole cut is no such bound (see mincut.rs).
+        if current_priority == 1 && current.exits_beside_cat() > 1 {
+            continue;
+        }
+
+        // Skip positions another line (or thread) has already expanded
+        let hash = match cat_move_result {
+            Some(p) => state.hash ^ cat_key(Position::from_idx(state.cat as usize)) ^ cat_key(p),
+            None => state.hash,
+        };
+        if !walls.is_empty() && !shared.insert_expanded(hash) {
+            continue;
+        }
+
+        // Explore next possible player moves from the board *after* the cat
+        // moved, placing and lifting each wall in turn on a distance field.
+        // Walls outside the cat's region cannot change anything.
+        let parent = expanded.len();
+        expanded.push(state);
+        let mut index = 0;
+        let mut field = DistanceField::new(&current);
+        let region = current.cat_region();
+
+        for r in 0..R {
+            for c in 0..C {
+                let p = Position::new(r, c);
+                // Check if player can place wall at 'p' in the board *after* cat moved
+                if !current.cells[p.idx()] && p != current.cat_position && region.contains(p) {
+                    // At the root each thread only takes its own share of the walls
+                    index += 1;
+                    if walls.is_empty() && index % parts != part {
+                        continue;
                     }
+
+                    field.add_wall(p); // Player places wall
+                    let min_cat_moves = field.cat_moves(current.cat_position);
+                    pq.push(SearchState::child(current.cat_position, min_cat_moves, hash ^ wall_key(p), parent, p));
+                    field.remove_wall(p);
                 }
-                self.cached_moves = path;
-                return self.cached_moves.pop_front().expect("Path should not be empty");
             }
+        }
+    }
 
-            // If cat escaped in simulation, this path is bad (heuristic is 0) - prune?
-             if current_state.board.cat_position.is_edge() {
-                  continue; // Don't explore states where cat has already escaped
-             }
+    PartialResult {
+        solution: None,
+        info,
+        best_cut,
+    }
+}
 
+// The wall after which the cat's reply leaves it furthest from the edge and
+// hardest to reach with a cut, for when the search ends before ranking any
+fn heuristic_move(board: &Board) -> Option<Position> {
+    strategies::legal_walls(board).into_iter().max_by_key(|&p| {
+        let mut next = board.clone();
+        next.cells.set(p.idx(), true);
+        if next.move_cat().is_none() {
+            return (N, Reverse(0));
+        }
+        (next.min_cat_moves(), Reverse(next.min_vertex_cut().size))
+    })
+}
 
-            // Explore next possible player moves from the state *after* the cat moved
-            let shared_current_ptr = Rc::new(current_state); // Share ownership for 'previous' links
-
-            for r in 0..R {
-                for c in 0..C {
-                    let p = Position::new(r, c);
-                    // Check if player can place wall at 'p' in the board *after* cat moved
-                    if !shared_current_ptr.board.cells[p.idx()] && p != shared_current_ptr.board.cat_position {
-                        let mut next_board = shared_current_ptr.board.clone();
-                        next_board.cells.set(p.idx(), true); // Player places wall
-
-                        let mut next_state = SearchState {
-                            board: next_board,
-                            cached_min_cat_moves: None, // Calculate later
-                            num_moves: shared_current_ptr.num_moves + 1,
-                            previous: Some(shared_current_ptr.clone()),
-                            move_taken: Some(p),
-                        };
-                         // Pre-calculate heuristic/priority
-                         next_state.min_cat_moves();
-                        pq.push(next_state);
-                    }
-                }
+impl Player for AutoPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        // The plan only holds while the cat replies as predicted
+        if let Some(expected) = self.expected_cat.take() {
+            if board.cat_position != expected && !self.plan.is_empty() {
+                eprintln!(
+                    "AutoPlayer expected the cat at {} but it is at {}, replanning.",
+                    expected, board.cat_position
+                );
+                self.plan.clear();
             }
         }
+        if let Some(step) = self.plan.pop_next() {
+            if !board.cells[step.wall.idx()] && step.wall != board.cat_position {
+                self.expected_cat = step.reply;
+                return Some(step.wall);
+            }
+            self.plan.clear(); // The board no longer matches the plan
+        }
+
+        let result = self.search(board, self.budget, &AtomicBool::new(false), &mut |_| {});
+
+        if result.is_proven() {
+            eprintln!("AutoPlayer found winning sequence after {} iterations! (proven)", result.info.nodes);
+        } else {
+            eprintln!(
+                "AutoPlayer found no solution after {} iterations, playing the best move so far (heuristic, cat distance {}).",
+                result.info.nodes, result.info.best_score
+            );
+        }
 
-         eprintln!("AutoPlayer failed to find a solution. Picking arbitrary move.");
-         // Fallback if queue empties (should indicate player cannot win)
-         for r in 0..R { for c in 0..C { let p = Position::new(r, c); if !board.cells[p.idx()] && p != board.cat_position { return p; } } }
-         return board.cat_position; // Should not happen
+        let best = result.best_move();
+        if let Some(plan) = result.solution {
+            self.plan = plan;
+            if let Err(e) = self.verify_plan(board, &mut DefaultCat) {
+                eprintln!("AutoPlayer's plan does not trap the cat: {}", e); // Should not happen
+            }
+            let step = self.plan.pop_next().expect("Plan should not be empty");
+            self.expected_cat = step.reply;
+            return Some(step.wall);
+        }
+        best
     }
 }
 
@@ -553,39 +1164,162 @@ impl Player for AutoPlayer {
 // --- Main Game Logic ---
 
 fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let tables = match load_tables(&mut args) {
+        Ok(tables) => tables,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
     if args.len() < 2 {
-        eprintln!("Usage: {} <input_file>", args[0]);
+        eprintln!("Usage: {} [--tablebase <file>] [--book <file>] <command>, where <command> is one of", args[0]);
+        eprintln!("       {} <input_file>", args[0]);
+        eprintln!("       {} human <input_file>", args[0]);
+        eprintln!("       {} cat <input_file> [{}]", args[0], strategies::WALL_STRATEGIES.join("|"));
+        eprintln!("       {} hotseat <input_file>", args[0]);
+        eprintln!("       {} host <input_file> [addr]", args[0]);
+        eprintln!("       {} join <addr>", args[0]);
+        eprintln!("       {} tournament [--walls a,b] [--cats x,y] [--boards N] [--seed S]", args[0]);
+        eprintln!("       {} record <input_file> [wall strategy] [cat strategy]", args[0]);
+        eprintln!("       {} analyze <record_file> [nodes]", args[0]);
+        eprintln!("       {} estimate <input_file> [walls[:noise]] [cat[:noise]] [games] [seed]", args[0]);
+        eprintln!("       {} tablebase <output_file> [max_size]", args[0]);
+        eprintln!("       {} book <output_file> [boards] [plies] [nodes] [seed]", args[0]);
+        eprintln!("       {} engine", args[0]);
+        eprintln!("       {} serve [addr]", args[0]);
         process::exit(1);
     }
 
-    let input_path = Path::new(&args[1]);
-    let file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
+    match args[1].as_str() {
+        "human" if args.len() >= 3 => play(Path::new(&args[2]), Box::new(InteractivePlayer)),
+        "cat" if args.len() >= 3 => {
+            play_as_cat(Path::new(&args[2]), args.get(3).map_or("auto", String::as_str), &tables)
+        }
+        "hotseat" if args.len() >= 3 => {
+            let board = Board::from_file(Path::new(&args[2]))?;
+            session::Session::shared(board, Box::new(session::TerminalSeat)).run()?;
+            Ok(())
+        }
+        "host" if args.len() >= 3 => {
+            host(Path::new(&args[2]), args.get(3).map_or("127.0.0.1:7878", String::as_str))
+        }
+        "join" if args.len() >= 3 => session::connect(&args[2]),
+        "tournament" => match tournament::Config::from_args(&args[2..], &tables) {
+            Ok(config) => {
+                tournament::print_report(&tournament::run(&config));
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        "record" if args.len() >= 3 => record(
+            Path::new(&args[2]),
+            args.get(3).map_or("auto", String::as_str),
+            args.get(4).map_or("default", String::as_str),
+            &tables,
+        ),
+        "analyze" if args.len() >= 3 => match args.get(3).map(|n| n.parse::<usize>()) {
+            None => analyze(Path::new(&args[2]), 5000),
+            Some(Ok(nodes)) => analyze(Path::new(&args[2]), nodes),
+            Some(Err(_)) => {
+                eprintln!("Invalid node count '{}'", args[3]);
+                process::exit(1);
+            }
+        },
+        "estimate" if args.len() >= 3 => {
+            let (Ok(games), Ok(seed)) = (
+                args.get(5).map_or(Ok(200), |n| n.parse::<usize>()),
+                args.get(6).map_or(Ok(0), |s| s.parse::<u64>()),
+            ) else {
+                eprintln!("Invalid game count or seed");
+                process::exit(1);
+            };
+            estimate(
+                Path::new(&args[2]),
+                args.get(3).map_or("greedy:0.1", String::as_str),
+                args.get(4).map_or("default:0.1", String::as_str),
+                games,
+                seed,
+                &tables,
+            )
+        }
+        "tablebase" if args.len() >= 3 => match args.get(3).map_or(Ok(7), |n| n.parse::<usize>()) {
+            Ok(max_size) if max_size <= tablebase::MAX_SIZE => write_tablebase(Path::new(&args[2]), max_size),
+            _ => {
+                eprintln!("Invalid region size (at most {})", tablebase::MAX_SIZE);
+                process::exit(1);
+            }
+        },
+        "book" if args.len() >= 3 => {
+            let numbers: Result<Vec<usize>, _> = args[3..].iter().map(|n| n.parse::<usize>()).collect();
+            match numbers.as_deref() {
+                Ok(&[]) => write_book(Path::new(&args[2]), 100, 3, 200000, 0),
+                Ok(&[boards]) => write_book(Path::new(&args[2]), boards, 3, 200000, 0),
+                Ok(&[boards, plies]) => write_book(Path::new(&args[2]), boards, plies, 200000, 0),
+                Ok(&[boards, plies, nodes]) => write_book(Path::new(&args[2]), boards, plies, nodes, 0),
+                Ok(&[boards, plies, nodes, seed]) => write_book(Path::new(&args[2]), boards, plies, nodes, seed as u64),
+                _ => {
+                    eprintln!("Invalid book size");
+                    process::exit(1);
+                }
+            }
+        }
+        "engine" => protocol::run(tables),
+        "serve" => serve(args.get(2).map_or("127.0.0.1:8080", String::as_str), tables),
+        input => play(Path::new(input), Box::new(tables.auto_player())),
+    }
+}
+
+// Plays a game between two TCP clients: the first to connect places the walls
+fn host(input_path: &Path, addr: &str) -> io::Result<()> {
+    let board = Board::from_file(input_path)?;
+    let listener = TcpListener::bind(addr)?;
+    println!("Waiting for the wall player on {}...", listener.local_addr()?);
+    let walls = session::TcpSeat::new(listener.accept()?.0)?;
+    println!("Waiting for the cat player...");
+    let cat = session::TcpSeat::new(listener.accept()?.0)?;
+    let status = session::Session::new(board, Box::new(walls), Box::new(cat)).run()?;
+    println!("Game over: {}", status);
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+fn serve(addr: &str, tables: strategies::Tables) -> io::Result<()> {
+    server::Server::new(tables).run(addr)
+}
+
+#[cfg(not(feature = "server"))]
+fn serve(_addr: &str, _tables: strategies::Tables) -> io::Result<()> {
+    eprintln!("This binary was built without the \"server\" feature.");
+    process::exit(1);
+}
 
-    let mut board = Board::from_reader(&mut reader)?;
+fn play(input_path: &Path, mut player: Box<dyn Player>) -> io::Result<()> {
+    let mut board = Board::from_file(input_path)?;
 
     println!("Initial Board:");
     println!("{}", board);
 
-    // Choose player type
-    // let mut player: Box<dyn Player> = Box::new(InteractivePlayer);
-     let mut player: Box<dyn Player> = Box::new(AutoPlayer::new());
-
     loop {
         // 1. Check if cat escaped edge (before player move)
-        if board.cat_position.is_edge() {
+        if board.has_cat_escaped() {
             println!("Failed! The cat ran away!");
             break;
         }
 
         // 2. Get Player's move
         println!("Player's turn...");
-        let player_move = loop {
-            let mv = player.get_move(&board);
+        loop {
+            let Some(mv) = player.get_move(&board) else {
+                println!("No wall can be placed.");
+                return Ok(());
+            };
             if board.move_player(mv) {
                 println!("Player places wall at: {}", mv);
-                break mv; // Valid move applied
+                break; // Valid move applied
             } else {
                 // This should ideally not happen with AutoPlayer if logic is correct
                  // For InteractivePlayer, re-prompt
@@ -594,7 +1328,7 @@ fn main() -> io::Result<()> {
                  // For now, assume InteractivePlayer might retry. If AutoPlayer fails, loop might continue forever.
                  // Maybe add logic here for AutoPlayer failure.
             }
-        };
+        }
 
         println!("Board after player's move:");
         println!("{}", board);
@@ -613,12 +1347,12 @@ fn main() -> io::Result<()> {
             }
             None => {
                 // Cat couldn't move
-                println!("Succeeded! You trapped the cat!");
+                println!("Succeeded! You trapped the cat in {} cells!", board.cat_region().size());
                 break; // Player wins
             }
         }
          // 5. Check if cat reached edge *after* its move
-         if board.cat_position.is_edge() {
+         if board.has_cat_escaped() {
               println!("Failed! The cat reached the edge after moving!");
               break; // Cat wins
          }
@@ -626,4 +1360,281 @@ fn main() -> io::Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Plays a game between two strategies and prints its record for `analyze`
+fn record(input_path: &Path, walls: &str, cat: &str, tables: &strategies::Tables) -> io::Result<()> {
+    let board = Board::from_file(input_path)?;
+    let Some(mut walls) = strategies::wall_player(walls, 0, tables) else {
+        eprintln!(
+            "{} (walls: {})",
+            strategies::wall_strategy_error(walls, tables),
+            strategies::WALL_STRATEGIES.join(", ")
+        );
+        process::exit(1);
+    };
+    let Some(mut cat) = strategies::cat_player(cat, 0) else {
+        eprintln!("Unknown cat strategy '{}' (cats: {})", cat, strategies::CAT_STRATEGIES.join(", "));
+        process::exit(1);
+    };
+    let result = game::play_game(board, walls.as_mut(), cat.as_mut());
+    eprintln!("Game over: {}", result.status);
+    print!("{}", result.record);
+    Ok(())
+}
+
+// Annotates every wall of a recorded game, searching `nodes` per position
+fn analyze(record_path: &Path, nodes: usize) -> io::Result<()> {
+    let record = game::GameRecord::from_reader(&mut BufReader::new(File::open(record_path)?))?;
+    let budget = SearchBudget {
+        nodes: Some(nodes),
+        ..SearchBudget::default()
+    };
+    match analysis::analyze(&record, budget) {
+        Ok(analysis) => analysis::print_analysis(&analysis),
+        Err(e) => {
+            eprintln!("Invalid record: {}", e);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+// Win probability of a wall policy against a cat policy from one board
+fn estimate(
+    input_path: &Path,
+    walls: &str,
+    cat: &str,
+    games: usize,
+    seed: u64,
+    tables: &strategies::Tables,
+) -> io::Result<()> {
+    let board = Board::from_file(input_path)?;
+    match estimate::estimate(&board, walls, cat, games, seed, tables) {
+        Ok(estimate) => println!("{} vs {}: {}", walls, cat, estimate),
+        Err(e) => {
+            eprintln!(
+                "{} (walls: {}; cats: {})",
+                e,
+                strategies::WALL_STRATEGIES.join(", "),
+                strategies::CAT_STRATEGIES.join(", ")
+            );
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+// Takes the "--tablebase <file>" and "--book <file>" options from the front
+// of the arguments and loads the files for every player the command builds
+fn load_tables(args: &mut Vec<String>) -> Result<strategies::Tables, String> {
+    let mut tables = strategies::Tables::default();
+    while args.len() >= 3 && (args[1] == "--tablebase" || args[1] == "--book") {
+        let path = args.remove(2);
+        match args.remove(1).as_str() {
+            "--tablebase" => {
+                let table = Tablebase::load(Path::new(&path))
+                    .map_err(|e| format!("Could not load tablebase {}: {}", path, e))?;
+                tables.tablebase = Some(Arc::new(table));
+            }
+            _ => {
+                let book = book::Book::load(Path::new(&path))
+                    .map_err(|e| format!("Could not load opening book {}: {}", path, e))?;
+                tables.book = Some(Arc::new(book));
+            }
+        }
+    }
+    Ok(tables)
+}
+
+// Solves the small regions for the search, see tablebase.rs
+fn write_tablebase(output_path: &Path, max_size: usize) -> io::Result<()> {
+    let start = Instant::now();
+    let table = Tablebase::generate(max_size);
+    table.save(output_path)?;
+    println!(
+        "{} won regions of up to {} cells in {:.1}s",
+        table.len(),
+        table.max_size(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+// Searches the openings of seeded boards for BookPlayer, see book.rs
+fn write_book(output_path: &Path, boards: usize, plies: usize, nodes: usize, seed: u64) -> io::Result<()> {
+    let start = Instant::now();
+    let book = book::Book::build(boards, plies, nodes, seed);
+    book.save(output_path)?;
+    println!("{} positions in {:.1}s", book.len(), start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+// Human plays the cat against an AI wall player
+fn play_as_cat(input_path: &Path, strategy: &str, tables: &strategies::Tables) -> io::Result<()> {
+    let mut player = match strategies::wall_player(strategy, 0, tables) {
+        Some(player) => player,
+        None => {
+            eprintln!(
+                "{} (expected one of {})",
+                strategies::wall_strategy_error(strategy, tables),
+                strategies::WALL_STRATEGIES.join(", ")
+            );
+            process::exit(1);
+        }
+    };
+    let mut cat = InteractiveCat;
+
+    let mut board = Board::from_file(input_path)?;
+
+    println!("Initial Board:");
+    println!("{}", board);
+
+    loop {
+        if board.has_cat_escaped() {
+            println!("Succeeded! The cat ran away!");
+            break;
+        }
+
+        println!("Wall player's turn ({})...", strategy);
+        let Some(mv) = player.get_move(&board) else {
+            println!("No wall can be placed.");
+            break;
+        };
+        if !board.move_player(mv) {
+            eprintln!("Wall player made an invalid move: {}. Stopping.", mv);
+            break;
+        }
+        println!("Wall placed at: {}", mv);
+        println!("{}", board);
+
+        if board.min_cat_moves() == N {
+            println!("Failed! The cat is trapped!");
+            break;
+        }
+
+        println!("Cat's turn...");
+        // get_move only returns None when the cat has no move, which a human cat never does
+        while let Some(target) = cat.get_move(&board) {
+            match board.move_cat_to(target) {
+                Ok(()) => {
+                    println!("Cat moved to: {}", target);
+                    break;
+                }
+                Err(e) => println!("{} Try again.", e),
+            }
+        }
+        println!("{}", board);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cat_moves_one_step_onto_empty_cells() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        board.cells.set(Position::new(5, 4).idx(), true);
+        assert_eq!(board.move_cat_to(Position::new(5, 7)), Err(CatMoveError::NotAdjacent(Position::new(5, 7))));
+        assert_eq!(board.move_cat_to(Position::new(5, 4)), Err(CatMoveError::Wall(Position::new(5, 4))));
+        assert_eq!(board.move_cat_to(Position::new(5, 5)), Err(CatMoveError::NotAdjacent(Position::new(5, 5))));
+        assert_eq!(board.cat_position, Position::new(5, 5));
+        assert_eq!(board.move_cat_to(Position::new(5, 6)), Ok(()));
+        assert_eq!(board.cat_position, Position::new(5, 6));
+
+        board.cat_position = Position::new(0, 0);
+        assert_eq!(board.move_cat_to(Position::new(-1, 0)), Err(CatMoveError::OffBoard(Position::new(-1, 0))));
+    }
+
+    // The cached plan is kept while the cat replies as predicted and dropped
+    // for a fresh search once it does not
+    #[test]
+    fn auto_player_replans_when_the_cat_leaves_the_line() {
+        let budget = SearchBudget {
+            nodes: Some(200),
+            ..SearchBudget::default()
+        };
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5);
+        let mut plan = Plan::new();
+        plan.push(Position::new(4, 5), Some(Position::new(5, 6)));
+        plan.push(Position::new(0, 0), Some(Position::new(5, 7)));
+        plan.push(Position::new(10, 0), Some(Position::new(5, 8)));
+
+        let mut player = AutoPlayer { plan, ..AutoPlayer::with_budget(budget) };
+        assert_eq!(player.get_move(&board), Some(Position::new(4, 5)));
+        board.cells.set(Position::new(4, 5).idx(), true);
+        board.cat_position = Position::new(5, 6);
+        assert_eq!(player.get_move(&board), Some(Position::new(0, 0)));
+
+        // The cat steps west instead of to (5, 7)
+        board.cells.set(Position::new(0, 0).idx(), true);
+        board.cat_position = Position::new(5, 5);
+        let replanned = player.get_move(&board);
+        assert_ne!(replanned, Some(Position::new(10, 0)));
+        assert_eq!(replanned, AutoPlayer::with_budget(budget).get_move(&board));
+    }
+
+    // One step from the edge with a cut of two, and still won: block the
+    // step at (0,5), then the way on from (2,5) at (3,5)
+    #[test]
+    fn a_cut_beyond_the_distance_is_not_pruned() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(1, 5);
+        for (r, c) in [(1, 4), (0, 6), (1, 6), (2, 6), (2, 4), (3, 4)] {
+            board.cells.set(Position::new(r, c).idx(), true);
+        }
+        assert_eq!(board.min_cat_moves(), 1);
+        assert_eq!(board.min_vertex_cut().size, 2);
+        let budget = SearchBudget {
+            nodes: Some(200),
+            ..SearchBudget::default()
+        };
+        let result = AutoPlayer::with_budget(budget).search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+        let plan = result.solution.expect("the pocket is won");
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.next().map(|step| step.wall), Some(Position::new(0, 5)));
+
+        // With a second exit beside it the cat is out whatever the walls do
+        board.cells.set(Position::new(0, 6).idx(), false);
+        let result = AutoPlayer::with_budget(budget).search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+        assert!(result.solution.is_none());
+        assert_eq!(result.info.nodes, 1);
+    }
+
+    // All threads together stay within the node budget, and with the cores
+    // for them get through more nodes in the same time than one thread
+    #[test]
+    fn threads_share_the_budget_and_scale() {
+        let mut board = Board::new();
+        board.cat_position = Position::new(5, 5); // Far from any proof
+        let nodes = |threads: usize, budget: SearchBudget| {
+            let player = AutoPlayer { threads, ..AutoPlayer::with_budget(budget) };
+            player.search(&board, budget, &AtomicBool::new(false), &mut |_| {}).info.nodes
+        };
+
+        let capped = SearchBudget {
+            nodes: Some(200),
+            ..SearchBudget::default()
+        };
+        assert!(nodes(4, capped) < 200 + 4);
+
+        let timed = SearchBudget {
+            time: Some(Duration::from_millis(1000)),
+            ..SearchBudget::default()
+        };
+        let cores = thread::available_parallelism().map_or(1, |n| n.get()).min(4);
+        let (one, four) = (nodes(1, timed), nodes(4, timed));
+        assert!(2 * four >= cores * one, "{} nodes with one thread, {} with four on {} cores", one, four, cores);
+    }
+
+    // A hash, a parent index and four bytes, with nothing on the heap
+    #[test]
+    fn search_states_stay_compact() {
+        assert!(std::mem::size_of::<SearchState>() <= 16);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque, BinaryHeap};
+use std::collections::{HashMap, HashSet, BinaryHeap};
 use std::cmp::Ordering;
 use std::fmt;
 use std::fs::File;
@@ -6,6 +6,12 @@ use std::io::{self, BufRead, BufReader, Write};
 use std::rc::Rc;
 use std::cell::RefCell;
 
+// The same play-order line main.rs keeps its plans in
+#[allow(dead_code)]
+#[path = "line.rs"]
+mod line;
+use line::Line;
+
 const R: i8 = 11;
 const C: i8 = 10;
 const N: usize = (R as usize) * (C as usize);
@@ -161,13 +167,13 @@ impl BasePlayer for InteractivePlayer {
 }
 
 struct AutoPlayer {
-    cached_moves: VecDeque<Position>,
+    cached_moves: Line<Position>,
 }
 
 impl AutoPlayer {
     fn new() -> Self {
         Self {
-            cached_moves: VecDeque::new(),
+            cached_moves: Line::new(),
         }
     }
 
@@ -254,7 +260,8 @@ impl AutoPlayer {
 
 impl BasePlayer for AutoPlayer {
     fn get_move(&mut self, board: &Board) -> Position {
-        if let Some(p) = self.cached_moves.pop_back() {
+        // cached_moves holds the rest of the winning line
+        if let Some(p) = self.cached_moves.pop_next() {
             return p;
         }
 
@@ -278,12 +285,12 @@ impl BasePlayer for AutoPlayer {
                 while let Some(state_ptr) = ptr {
                     let state = state_ptr.borrow();
                     if let Some(mv) = state.mv {
-                        self.cached_moves.push_front(mv);
+                        self.cached_moves.push_first(mv);
                     }
                     ptr = state.previous.clone();
                 }
 
-                return self.cached_moves.pop_back().unwrap();
+                return self.cached_moves.pop_next().unwrap();
             }
 
             for r in 0..R {
@@ -2,7 +2,7 @@ use bitvec::prelude::*;
 use once_cell::sync::Lazy; // Or use std::sync::OnceLock
 use std::cell::Cell;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::env;
 use std::error::Error;
 use std::fmt;
@@ -13,6 +13,12 @@ use std::ops::{Index, IndexMut};
 use std::rc::Rc; // Using Rc as the C++ code seems single-threaded with shared_ptr
 use std::str::FromStr;
 
+// The same play-order line main.rs keeps its plans in
+#[allow(dead_code)]
+#[path = "line.rs"]
+mod line;
+use line::Line;
+
 // Type alias equivalent to using int_t = int8_t;
 type IntT = i8;
 
@@ -638,14 +644,14 @@ impl fmt::Display for SearchState {
 
 
 struct AutoPlayer {
-    // Use VecDeque for efficient front/back operations like std::deque
-    cached_moves: VecDeque<Position>,
+    // The rest of the winning line, in play order
+    cached_moves: Line<Position>,
 }
 
 impl AutoPlayer {
     fn new() -> Self {
         AutoPlayer {
-            cached_moves: VecDeque::new(),
+            cached_moves: Line::new(),
         }
     }
 }
@@ -653,10 +659,8 @@ impl AutoPlayer {
 impl BasePlayer for AutoPlayer {
     // Logic ported from C++ AutoPlayer::get_move
     fn get_move(&mut self, board: &Board) -> Position {
-        // Return cached move if available (note: C++ pops back, Rust pops front/back)
-        // C++ pushes back, then pops back (LIFO). Rust should push_front, pop_front (FIFO)
-        // or push_back, pop_back (LIFO). Let's match C++ LIFO.
-        if let Some(mv) = self.cached_moves.pop_back() {
+        // Return cached move if available
+        if let Some(mv) = self.cached_moves.pop_next() {
              eprintln!("Using cached move: {}", mv); // Added for clarity
             return mv;
         }
@@ -721,13 +725,13 @@ impl BasePlayer for AutoPlayer {
              // Recalculate if cache was invalidated
              if current_state.min_cat_moves() == N as IntT {
                  eprintln!("Solution found!");
-                 let mut path = VecDeque::new(); // Use VecDeque to push front easily
+                 let mut path = Line::new(); // Built from the winning state back
                  let mut temp_ptr = Some(current_ptr.clone()); // Start with the winning state
 
                  // Walk back through the 'previous' links
                  while let Some(state_rc) = temp_ptr {
                      if let Some(mv) = state_rc.move_made {
-                         path.push_front(mv); // Add move to the front of the path
+                         path.push_first(mv); // Add move to the front of the path
                      }
                      // Move to the previous state in the chain
                      temp_ptr = state_rc.previous.clone(); // Clone the Option<Rc<...>>
@@ -737,7 +741,7 @@ impl BasePlayer for AutoPlayer {
                  self.cached_moves = path; // Store the calculated path
 
                  // Return the first move from the path
-                 if let Some(mv) = self.cached_moves.pop_front() { // First move is at the front
+                 if let Some(mv) = self.cached_moves.pop_next() {
                      return mv;
                  } else {
                      // Should not happen if a solution was found with moves
@@ -0,0 +1,293 @@
+// --- Monte Carlo Tree Search wall player ---
+//
+// Open-loop UCT: tree nodes are reached by sequences of walls, and the cat's
+// replies are re-simulated on every iteration with the configured cat strategy,
+// so stochastic cats work too. Playouts alternate a light wall policy (block
+// the cat's next step or wall near it) with the same cat strategy.
+
+use crate::game::{Game, GameStatus};
+use crate::rng::Rng;
+use crate::strategies::legal_walls;
+use crate::{Board, CatPlayer, Player, Position, N};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+pub struct MctsConfig {
+    pub playouts: usize,
+    pub time_budget: Option<Duration>,
+    pub exploration: f64, // UCT exploration constant
+    pub max_playout_walls: usize,
+    pub block_probability: f64, // Chance a playout wall blocks the cat's next step
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            playouts: 20000,
+            time_budget: Some(Duration::from_secs(2)),
+            exploration: 1.0,
+            max_playout_walls: 30,
+            block_probability: 0.8,
+            seed: 0,
+        }
+    }
+}
+
+// Candidate walls are limited to empty cells this close to the cat
+const CANDIDATE_RADIUS: usize = 3;
+
+struct Node {
+    untried: Vec<Position>,
+    children: Vec<(Position, usize)>, // Wall and index of the child node
+    visits: u32,
+    wins: f64,
+}
+
+impl Node {
+    fn new(board: &Board) -> Self {
+        let mut untried = candidate_walls(board);
+        untried.reverse(); // Expand the cells closest to the cat first
+        Node {
+            untried,
+            children: Vec::new(),
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+pub struct MctsPlayer {
+    config: MctsConfig,
+    cat: Box<dyn CatPlayer>,
+    rng: Rng,
+}
+
+impl MctsPlayer {
+    pub fn new(config: MctsConfig, cat: Box<dyn CatPlayer>) -> Self {
+        MctsPlayer {
+            rng: Rng::new(config.seed),
+            config,
+            cat,
+        }
+    }
+
+    // One selection/expansion/playout/backpropagation pass
+    fn iterate(&mut self, nodes: &mut Vec<Node>, root: &Board) {
+        let mut game = Game::new(root.clone());
+        let mut path = vec![0];
+        let mut current = 0;
+        let mut forfeit = false; // The cat strategy made an illegal move
+
+        // Selection: descend through fully expanded nodes
+        while !forfeit && game.status() == GameStatus::Playing && nodes[current].untried.is_empty() {
+            let parent_visits = nodes[current].visits.max(1) as f64;
+            let exploration = self.config.exploration;
+            let best = nodes[current]
+                .children
+                .iter()
+                .filter(|(p, _)| !game.board.cells[p.idx()] && *p != game.board.cat_position)
+                .max_by(|(_, a), (_, b)| {
+                    let uct = |i: usize| {
+                        let n = &nodes[i];
+                        let visits = n.visits.max(1) as f64;
+                        n.wins / visits + exploration * (parent_visits.ln() / visits).sqrt()
+                    };
+                    uct(*a).total_cmp(&uct(*b))
+                })
+                .copied();
+            match best {
+                Some((p, child)) => {
+                    forfeit = !self.play(&mut game, p);
+                    current = child;
+                    path.push(child);
+                }
+                None => break, // No candidate is legal in this simulation
+            }
+        }
+
+        // Expansion: try one new wall
+        if !forfeit && game.status() == GameStatus::Playing {
+            while let Some(p) = nodes[current].untried.pop() {
+                if game.board.cells[p.idx()] || p == game.board.cat_position {
+                    continue;
+                }
+                forfeit = !self.play(&mut game, p);
+                let child = nodes.len();
+                nodes.push(Node::new(&game.board));
+                nodes[current].children.push((p, child));
+                path.push(child);
+                break;
+            }
+        }
+
+        let reward = if forfeit { 1.0 } else { self.playout(&mut game) };
+        for &i in &path {
+            nodes[i].visits += 1;
+            nodes[i].wins += reward;
+        }
+    }
+
+    // Places a wall and lets the cat strategy answer. Returns false if the
+    // answer is illegal, which forfeits the game for the cat as in play_game.
+    fn play(&mut self, game: &mut Game, p: Position) -> bool {
+        if !matches!(game.place_wall(p), Ok(GameStatus::Playing)) {
+            return true;
+        }
+        match self.cat.get_move(&game.board) {
+            Some(target) => game.move_cat_to(target).is_ok(),
+            None => false,
+        }
+    }
+
+    fn playout(&mut self, game: &mut Game) -> f64 {
+        for _ in 0..self.config.max_playout_walls {
+            if game.status() != GameStatus::Playing {
+                break;
+            }
+            let Some(p) = self.playout_wall(&game.board) else {
+                break;
+            };
+            if !self.play(game, p) {
+                return 1.0;
+            }
+        }
+        match game.status() {
+            GameStatus::Won => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    // Usually blocks the cat's next step, otherwise walls a random candidate
+    // cell; None only if the cat has no empty neighbour, so is trapped
+    fn playout_wall(&mut self, board: &Board) -> Option<Position> {
+        if self.rng.next_f64() < self.config.block_probability {
+            if let Some(p) = board.best_cat_move() {
+                return Some(p);
+            }
+        }
+        let candidates = candidate_walls(board);
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[self.rng.below(candidates.len())])
+    }
+}
+
+impl Player for MctsPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let start = Instant::now();
+        let mut nodes = vec![Node::new(board)];
+        for _ in 0..self.config.playouts {
+            if self.config.time_budget.is_some_and(|t| start.elapsed() >= t) {
+                break;
+            }
+            self.iterate(&mut nodes, board);
+        }
+
+        let root = &nodes[0];
+        eprintln!(
+            "MctsPlayer ran {} playouts in {:.2}s",
+            root.visits,
+            start.elapsed().as_secs_f64()
+        );
+        root.children
+            .iter()
+            .max_by_key(|(_, child)| nodes[*child].visits)
+            .map(|&(p, _)| p)
+            .or_else(|| candidate_walls(board).first().copied())
+            .or_else(|| legal_walls(board).first().copied())
+    }
+}
+
+// Empty cells the cat can reach within CANDIDATE_RADIUS steps. Walls further
+// away, or outside the cat's region, rarely matter.
+fn candidate_walls(board: &Board) -> Vec<Position> {
+    let mut distance = vec![N; N];
+    let mut queue = std::collections::VecDeque::new();
+    let mut ret = Vec::new();
+    distance[board.cat_position.idx()] = 0;
+    queue.push_back(board.cat_position);
+    while let Some(p) = queue.pop_front() {
+        if distance[p.idx()] == CANDIDATE_RADIUS {
+            continue;
+        }
+        for &pp in board.neighbors(p) {
+            if !board.cells[pp.idx()] && distance[pp.idx()] == N {
+                distance[pp.idx()] = distance[p.idx()] + 1;
+                ret.push(pp);
+                queue.push_back(pp);
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{play_game, GameStatus};
+    use crate::strategies::{RandomCat, RandomPlayer};
+    use crate::DefaultCat;
+
+    // Records how long the wrapped player takes over its slowest move
+    struct Timed<'a> {
+        player: &'a mut dyn Player,
+        slowest: Duration,
+    }
+
+    impl Player for Timed<'_> {
+        fn get_move(&mut self, board: &Board) -> Option<Position> {
+            let start = Instant::now();
+            let p = self.player.get_move(board);
+            self.slowest = self.slowest.max(start.elapsed());
+            p
+        }
+    }
+
+    // On open random boards, out of reach of the exact search, a hundred
+    // playouts a move trap a random cat that random walls never trap. The
+    // count is fixed so the games do not depend on the machine's speed, and
+    // since the tree models the cat as DefaultCat, one game in six may still
+    // be lost. With unlimited playouts the time budget alone ends every move.
+    #[test]
+    fn mcts_beats_random_walls_within_its_budget() {
+        let budget = Duration::from_millis(50);
+        let mut wins = 0;
+        for seed in 0..6 {
+            let config = MctsConfig {
+                playouts: 100,
+                time_budget: None,
+                seed,
+                ..MctsConfig::default()
+            };
+            let mut mcts = MctsPlayer::new(config, Box::new(DefaultCat));
+            let result = play_game(Board::random(seed), &mut mcts, &mut RandomCat::new(seed));
+            if result.status == GameStatus::Won {
+                wins += 1;
+            }
+
+            let result = play_game(
+                Board::random(seed),
+                &mut RandomPlayer::new(seed),
+                &mut RandomCat::new(seed),
+            );
+            assert_eq!(result.status, GameStatus::Lost, "seed {}", seed);
+
+            let config = MctsConfig {
+                playouts: usize::MAX,
+                time_budget: Some(budget),
+                seed,
+                ..MctsConfig::default()
+            };
+            let mut mcts = MctsPlayer::new(config, Box::new(DefaultCat));
+            let mut timed = Timed {
+                player: &mut mcts,
+                slowest: Duration::ZERO,
+            };
+            play_game(Board::random(seed), &mut timed, &mut RandomCat::new(seed));
+            assert!(timed.slowest < 4 * budget, "seed {}: {:?}", seed, timed.slowest);
+        }
+        assert!(wins >= 5, "{} of 6 won", wins);
+    }
+}
@@ -0,0 +1,248 @@
+// --- Minimum vertex cut between the cat and the edge ---
+//
+// The fewest walls that separate the cat from every edge cell, found as a
+// max-flow on the node-split grid: each empty cell becomes an in-node and an
+// out-node joined by a capacity-1 arc, adjacent empty cells are joined
+// out -> in with unbounded capacity, and empty edge cells drain into a sink.
+// The cat has at most MAX_DEGREE neighbours, so at most that many augmenting
+// paths.
+//
+// The graph is never built: arcs are derived from the board on the fly, and
+// the flow is kept per cell, which keeps this cheap enough to run on every
+// search node.
+//
+// The AutoPlayer search uses the cut to break ties between states at the same
+// distance and to finish a line when a single wall is left to place. It does
+// not prune on it: the walls place one wall per cat step, but a wall that
+// lengthens the cat's path also buys a turn, so a cut larger than the distance
+// can still be won. A cat one step from the edge, with a second way out
+// behind it, has a cut of two and is trapped by blocking the step and then the
+// second way. Only the cut of the cat's next step, the empty edge cells beside
+// it, bounds what the walls can still do.
+
+use crate::topology::{Topology, MAX_DEGREE};
+use crate::{Board, Position, N};
+use std::collections::VecDeque;
+
+const SINK: usize = 2 * N;
+
+fn in_node(p: Position) -> usize {
+    2 * p.idx()
+}
+
+fn out_node(p: Position) -> usize {
+    2 * p.idx() + 1
+}
+
+fn cell(node: usize) -> Position {
+    Position::from_idx(node / 2)
+}
+
+fn is_out(node: usize) -> bool {
+    node % 2 == 1
+}
+
+struct Flow<'a> {
+    topology: &'a Topology,
+    open: [bool; N],                 // Empty and not the cat, copied out of the board once
+    through: [bool; N],              // The cell's in -> out arc carries a unit
+    out_flow: [[u8; MAX_DEGREE]; N], // Units sent from the cell's out-node to each neighbour's in-node, by slot
+}
+
+impl Flow<'_> {
+    fn is_open(&self, p: Position) -> bool {
+        self.open[p.idx()]
+    }
+
+    // Where q is in the list of p's neighbours
+    fn slot(&self, p: Position, q: Position) -> usize {
+        self.topology
+            .neighbors(p)
+            .iter()
+            .position(|&n| n == q)
+            .expect("Cells should be adjacent")
+    }
+
+    // Nodes reachable from `node` along arcs with residual capacity
+    fn residual_arcs(&self, node: usize, mut visit: impl FnMut(usize)) {
+        let p = cell(node);
+        if is_out(node) {
+            for &q in self.topology.neighbors(p) {
+                if self.is_open(q) {
+                    visit(in_node(q));
+                }
+            }
+            if self.topology.is_exit(p) {
+                visit(SINK);
+            }
+            if self.through[p.idx()] {
+                visit(in_node(p));
+            }
+        } else {
+            if !self.through[p.idx()] {
+                visit(out_node(p));
+            }
+            // Flow arriving from a neighbour q can be sent back
+            for (s, &q) in self.topology.neighbors(p).iter().enumerate() {
+                if self.out_flow[q.idx()][self.topology.back(p, s)] > 0 {
+                    visit(out_node(q));
+                }
+            }
+        }
+    }
+
+    // BFS over the residual graph; returns the predecessor of each reached node
+    fn residual_bfs(&self, source: usize) -> Vec<Option<usize>> {
+        let mut previous: Vec<Option<usize>> = vec![None; SINK + 1];
+        previous[source] = Some(source);
+        let mut queue = VecDeque::new();
+        queue.push_back(source);
+
+        while let Some(node) = queue.pop_front() {
+            if node == SINK {
+                break;
+            }
+            self.residual_arcs(node, |next| {
+                if previous[next].is_none() {
+                    previous[next] = Some(node);
+                    queue.push_back(next);
+                }
+            });
+        }
+        previous
+    }
+
+    // Pushes one unit along a shortest augmenting path, if there is one
+    fn augment(&mut self, source: usize) -> bool {
+        let previous = self.residual_bfs(source);
+        if previous[SINK].is_none() {
+            return false;
+        }
+
+        let mut node = SINK;
+        while node != source {
+            let prev = previous[node].expect("Path nodes should have predecessors");
+            if node != SINK {
+                let (p, q) = (cell(prev), cell(node));
+                match (is_out(prev), p == q) {
+                    (false, true) => self.through[p.idx()] = true, // in -> out
+                    (true, true) => self.through[p.idx()] = false, // Undoing in -> out
+                    (true, false) => self.out_flow[p.idx()][self.slot(p, q)] += 1,
+                    (false, false) => self.out_flow[q.idx()][self.slot(q, p)] -= 1, // Undoing q -> p
+                }
+            }
+            node = prev;
+        }
+        true
+    }
+}
+
+// A smallest set of empty cells that separates the cat from the edge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexCut {
+    pub size: usize, // 0 if the cat is already trapped, N if it stands on the edge
+    pub cells: Vec<Position>, // One minimum cut, in board order (empty when size is N)
+}
+
+pub fn min_vertex_cut(board: &Board) -> VertexCut {
+    if board.has_cat_escaped() {
+        return VertexCut {
+            size: N,
+            cells: Vec::new(),
+        };
+    }
+
+    let source = out_node(board.cat_position);
+    let mut open = [false; N];
+    for (i, open) in open.iter_mut().enumerate() {
+        *open = !board.cells[i];
+    }
+    open[board.cat_position.idx()] = false;
+    let mut flow = Flow {
+        topology: &board.topology,
+        open,
+        through: [false; N],
+        out_flow: [[0; MAX_DEGREE]; N],
+    };
+    let mut size = 0;
+    while flow.augment(source) {
+        size += 1;
+    }
+
+    // Saturated cells on the boundary of what the source can still reach
+    let reachable = flow.residual_bfs(source);
+    let cells = (0..N)
+        .map(Position::from_idx)
+        .filter(|&p| flow.is_open(p) && reachable[in_node(p)].is_some() && reachable[out_node(p)].is_none())
+        .collect();
+    VertexCut { size, cells }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // Whether the cat can still reach the edge with `cut` walled as well
+    fn escapes(board: &Board, cut: &[Position]) -> bool {
+        let mut walled = board.clone();
+        for &p in cut {
+            walled.cells.set(p.idx(), true);
+        }
+        walled.min_cat_moves() != N
+    }
+
+    // Smallest cut by trying every set of open cells, smallest first
+    fn brute_force_cut(board: &Board, open: &[Position]) -> usize {
+        (0..=open.len())
+            .find(|&size| {
+                (0u32..1 << open.len())
+                    .filter(|set| set.count_ones() as usize == size)
+                    .any(|set| {
+                        let cut: Vec<Position> = (0..open.len())
+                            .filter(|&i| set & 1 << i != 0)
+                            .map(|i| open[i])
+                            .collect();
+                        !escapes(board, &cut)
+                    })
+            })
+            .expect("Walling every open cell traps the cat")
+    }
+
+    // Boards walled but for a few random cells around the cat, near the edge
+    // so that some of them escape
+    #[test]
+    fn cut_matches_brute_force_on_small_boards() {
+        let mut rng = Rng::new(33);
+        let cats = [
+            Position::new(2, 2),
+            Position::new(1, 5),
+            Position::new(8, 7),
+            Position::new(5, 2),
+        ];
+        for seed in 0..200 {
+            let mut board = Board::new();
+            board.cat_position = cats[seed % cats.len()];
+            let around = board.neighbors(board.cat_position);
+            let near: Vec<Position> = around
+                .iter()
+                .flat_map(|&p| board.neighbors(p))
+                .chain(around)
+                .copied()
+                .collect();
+            for p in (0..N).map(Position::from_idx) {
+                let open = p == board.cat_position || (near.contains(&p) && rng.below(4) != 0);
+                board.cells.set(p.idx(), !open);
+            }
+            let open: Vec<Position> = (0..N)
+                .map(Position::from_idx)
+                .filter(|&p| !board.cells[p.idx()] && p != board.cat_position)
+                .collect();
+
+            let cut = min_vertex_cut(&board);
+            assert_eq!(cut.size, brute_force_cut(&board, &open), "seed {}\n{}", seed, board);
+            assert_eq!(cut.cells.len(), cut.size, "seed {}\n{}", seed, board);
+            assert!(!escapes(&board, &cut.cells), "seed {}\n{}", seed, board);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+// --- Winning plans ---
+//
+// A plan is the rest of a winning line found by the search, in play order:
+// the front step is the next wall to place, the back step the one that traps
+// the cat. Steps are only ever taken from the front. Each wall comes with the
+// cat's position after its reply as the search predicted it, so a player can
+// tell when the real cat has left the line. The order itself is kept by
+// `Line`, which the plan wraps.
+
+use crate::game::{Game, GameStatus, MoveError};
+use crate::line::Line;
+use crate::{Board, CatPlayer, Position};
+use std::fmt::{self, Display};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlanStep {
+    pub wall: Position,
+    pub reply: Option<Position>, // Predicted cat position after the wall, None once trapped
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    steps: Line<PlanStep>,
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Plan { steps: Line::new() }
+    }
+
+    // Adds a step after all the others
+    pub fn push(&mut self, wall: Position, reply: Option<Position>) {
+        self.steps.push(PlanStep { wall, reply });
+    }
+
+    // Adds a step before all the others, for building a plan from its end
+    pub fn push_first(&mut self, wall: Position, reply: Option<Position>) {
+        self.steps.push_first(PlanStep { wall, reply });
+    }
+
+    // The step to play now, without taking it
+    pub fn next(&self) -> Option<PlanStep> {
+        self.steps.next().copied()
+    }
+
+    // Takes the step to play now
+    pub fn pop_next(&mut self) -> Option<PlanStep> {
+        self.steps.pop_next()
+    }
+
+    // Adds the steps of `rest` after all the others
+    pub fn append(&mut self, rest: Plan) {
+        self.steps.append(rest.steps);
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    // Plays the plan's walls from `board` against `cat` until the cat is
+    // trapped. Returns the number of walls it took, which can be fewer than
+    // the plan holds if the cat walks into a trap early.
+    pub fn verify(&self, board: &Board, cat: &mut dyn CatPlayer) -> Result<usize, PlanError> {
+        let mut game = Game::new(board.clone());
+        for (i, step) in self.steps.iter().enumerate() {
+            match game.place_wall(step.wall) {
+                Ok(GameStatus::Won) => return Ok(i + 1),
+                Ok(_) => {}
+                Err(error) => return Err(PlanError::Wall { step: i, error }),
+            }
+            let Some(target) = cat.get_move(&game.board) else {
+                break; // A cat without a move is not necessarily trapped
+            };
+            match game.move_cat_to(target) {
+                Ok(GameStatus::Lost) => return Err(PlanError::Escaped { step: i, cat: target }),
+                Ok(GameStatus::Won) => return Ok(i + 1),
+                Ok(_) => {}
+                Err(error) => return Err(PlanError::Cat { step: i, error }),
+            }
+        }
+        Err(PlanError::Unfinished {
+            cat: game.board.cat_position,
+            distance: game.board.min_cat_moves(),
+        })
+    }
+}
+
+// Why a plan does not trap the cat. Steps count from 0.
+#[derive(Debug)]
+pub enum PlanError {
+    Wall { step: usize, error: MoveError }, // The wall cannot be placed
+    Cat { step: usize, error: MoveError },  // The cat answered the wall with an illegal move
+    Escaped { step: usize, cat: Position }, // The cat reached the edge after the wall
+    Unfinished { cat: Position, distance: usize }, // Out of steps with the cat still free
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Wall { step, error } => write!(f, "Step {}: {}", step + 1, error),
+            PlanError::Cat { step, error } => write!(f, "Step {}: the cat moved illegally: {}", step + 1, error),
+            PlanError::Escaped { step, cat } => write!(f, "Step {}: the cat escaped at {}", step + 1, cat),
+            PlanError::Unfinished { cat, distance } => write!(
+                f,
+                "The plan ended with the cat at {}, {} moves from the edge",
+                cat, distance
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AutoPlayer, DefaultCat, Player, SearchBudget};
+    use std::sync::atomic::AtomicBool;
+
+    const INPUTS: [&str; 7] = [
+        include_str!("input1.txt"),
+        include_str!("input2.txt"),
+        include_str!("input3.txt"),
+        include_str!("input4.txt"),
+        include_str!("input5.txt"),
+        include_str!("input6.txt"),
+        include_str!("input7.txt"),
+    ];
+
+    fn input(i: usize) -> Board {
+        Board::from_reader(&mut INPUTS[i].as_bytes()).expect("Inputs should parse")
+    }
+
+    #[test]
+    fn steps_are_taken_in_play_order() {
+        let mut plan = Plan::new();
+        plan.push(Position::new(1, 1), Some(Position::new(2, 2)));
+        plan.push(Position::new(3, 3), None);
+        plan.push_first(Position::new(4, 4), Some(Position::new(5, 5)));
+        let walls: Vec<_> = std::iter::from_fn(|| plan.pop_next()).map(|step| step.wall).collect();
+        assert_eq!(walls, [Position::new(4, 4), Position::new(1, 1), Position::new(3, 3)]);
+    }
+
+    // Splitting the root between threads must not lose a proof. The budget
+    // is the same total for both, and enough for four threads to prove the
+    // inputs one thread proves in under a thousand nodes; the others take
+    // tens of thousands.
+    #[test]
+    fn threads_prove_what_one_thread_proves() {
+        let budget = SearchBudget {
+            nodes: Some(3000),
+            ..SearchBudget::default()
+        };
+        for i in [0, 1, 2, 6] {
+            let board = input(i);
+            for threads in [1, 4] {
+                let player = AutoPlayer {
+                    threads,
+                    ..AutoPlayer::with_budget(budget)
+                };
+                let result = player.search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+                assert!(result.is_proven(), "input{} with {} threads", i + 1, threads);
+            }
+        }
+    }
+
+    // Every plan the search proves must trap the cat it was planned against,
+    // and keep doing so as the player takes its walls one by one. These are
+    // the inputs proven within the budget; `every_input_has_a_winning_line`
+    // covers the rest.
+    #[test]
+    fn proven_plans_trap_the_cat() {
+        let budget = SearchBudget {
+            nodes: Some(1000),
+            ..SearchBudget::default()
+        };
+        for i in [0, 1, 2, 6] {
+            let board = input(i);
+            let player = AutoPlayer::with_budget(budget);
+            let result = player.search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+            let plan = result
+                .solution
+                .unwrap_or_else(|| panic!("input{} should be proven", i + 1));
+            let walls = plan.verify(&board, &mut DefaultCat);
+            assert!(matches!(walls, Ok(n) if n <= plan.len()), "input{}: {:?}", i + 1, walls);
+            assert_eq!(play(board, plan), GameStatus::Won, "input{}", i + 1);
+        }
+    }
+
+    // A winning line for each input, as the search found it with a budget of
+    // tens of thousands of nodes for input4 to input6. Playing them takes no
+    // search, so every input has a full game here that is not ignored.
+    const LINES: [&str; 7] = [
+        "6 7, 5 7, 5 6, 5 8, 2 8, 4 7",
+        "1 2, 4 2, 3 1, 1 3, 2 5, 3 5, 5 4, 5 1, 7 1, 7 2, 8 3, 8 5, 6 7, 7 6, 4 7",
+        "2 8, 5 7, 4 8, 3 8, 1 7, 1 5, 3 4, 5 5, 6 6",
+        "2 6, 6 9, 3 7, 2 7, 2 5, 3 3, 4 3, 4 2, 6 2, 4 1, 6 3, 6 4, 6 5, 6 6, 6 7, 6 8",
+        "7 4, 6 2, 4 2, 4 1, 2 4, 3 2, 2 3, 7 2, 7 3, 7 5, 7 7, 7 6",
+        "9 8, 9 5, 9 6, 9 7, 8 9, 6 9, 8 5, 7 4, 6 5, 5 4",
+        "5 6, 4 9, 2 7, 2 8, 6 9, 6 8, 6 7, 4 6",
+    ];
+
+    #[test]
+    fn every_input_has_a_winning_line() {
+        for (i, line) in LINES.iter().enumerate() {
+            let board = input(i);
+            let mut plan = Plan::new();
+            for wall in line.split(", ") {
+                let (r, c) = wall.split_once(' ').expect("Walls are written as \"row col\"");
+                plan.push(Position::new(r.parse().unwrap(), c.parse().unwrap()), None);
+            }
+            let walls = plan.verify(&board, &mut DefaultCat);
+            assert_eq!(walls.ok(), Some(plan.len()), "input{}", i + 1);
+            assert_eq!(play(board, plan), GameStatus::Won, "input{}", i + 1);
+        }
+    }
+
+    // Plays a game against DefaultCat with an AutoPlayer that starts out
+    // holding `plan`, checking the rest of the plan after every cat move
+    fn play(board: Board, plan: Plan) -> GameStatus {
+        let budget = SearchBudget {
+            nodes: Some(1000),
+            ..SearchBudget::default()
+        };
+        let mut player = AutoPlayer {
+            plan,
+            ..AutoPlayer::with_budget(budget)
+        };
+        let mut game = Game::new(board);
+        while game.status() == GameStatus::Playing {
+            let wall = player.get_move(&game.board).expect("The walls should have a move");
+            if game.place_wall(wall).expect("Plan walls should be legal") != GameStatus::Playing {
+                break;
+            }
+            let cat = DefaultCat.get_move(&game.board).expect("The cat should have a move");
+            game.move_cat_to(cat).expect("DefaultCat should move legally");
+            if !player.plan.is_empty() {
+                let rest = player.verify_plan(&game.board, &mut DefaultCat);
+                assert!(rest.is_ok(), "{:?}", rest);
+            }
+        }
+        game.status()
+    }
+
+    // Full games over every input; slow without optimisations
+    #[test]
+    #[ignore]
+    fn auto_player_wins_every_input() {
+        for i in 0..INPUTS.len() {
+            let result = crate::game::play_game(input(i), &mut AutoPlayer::new(), &mut DefaultCat);
+            assert_eq!(result.status, GameStatus::Won, "input{}", i + 1);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// --- Line-based engine protocol over stdin/stdout ---
+//
+// A UCI-like protocol for GUIs and tournament tools. Moves are written "r,c".
+// Searches probe the tablebase given on the command line, if any.
+//
+//   position <notation>      set up a board (rows separated by '/', see Board::to_notation)
+//   position random <seed>   set up a standard random board
+//   walls <r,c> ...          play walls on the current board, each answered by the cat
+//   go [nodes N] [movetime ms] [memory MB] [infinite]
+//                            search in the background, printing "info" lines and
+//                            finally "bestmove <r,c>" (or "bestmove none")
+//   setoption threads <n>    number of search threads for later searches (default 1)
+//   stop                     stop the running search early
+//   bestmove                 wait for the running search, or repeat the last result
+//
+// While a search without limits runs (and has not been stopped), every
+// command but stop, isready and quit is answered with "error search running",
+// since waiting for it would block the stop.
+//
+//   eval                     print the static evaluation of the current board, with
+//                            the number of shortest escape paths and the one the cat takes
+//   field <distance|paths>   print every cell's distance to the edge, or its number
+//                            of shortest paths there ('#' for walls and cut-off cells)
+//   heatmap                  print the board with each cell's criticality (0-9) for a
+//                            wall, '*' marking articulation points of the cat's region
+//   region [k]               print the cat's region: its size, whether it touches the
+//                            edge, its articulation points and, given k, whether the
+//                            cat is enclosed in k cells or fewer
+//   hex <r,c>                print a cell's axial (q,r) and cube (x,y,z) coordinates
+//                            and its hex distance to the nearest edge cell
+//   hex ring <r,c> <k>       print the cells k steps from a cell
+//   hex line <r,c> <r,c>     print the cells on the straight line between two cells
+//   hex rotate <r,c> <r,c> <n>
+//                            turn a cell n times 60 degrees clockwise about another
+//   board                    print the current board
+//   hash                     print the board's Zobrist hash, the hash it shares with
+//                            its mirror images and the symmetry that gives that one
+//   isready                  answered with "readyok"
+//   quit                     stop any search and exit
+
+use crate::hex::Axial;
+use crate::strategies::Tables;
+use crate::symmetry;
+use crate::topology::Topology;
+use crate::{Board, Position, SearchBudget, SearchInfo, SearchResult, N};
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<SearchResult>,
+    unlimited: bool, // Only ends when stopped or the position is solved
+}
+
+struct Engine {
+    board: Option<Board>,
+    search: Option<RunningSearch>,
+    last_best: Option<Position>,
+    threads: usize,
+    tables: Tables,
+}
+
+pub fn run(tables: Tables) -> io::Result<()> {
+    let mut engine = Engine::new(tables);
+    println!("id name trapthecat");
+    println!("protocolok");
+
+    for line in io::stdin().lock().lines() {
+        if !engine.handle(&line?) {
+            break;
+        }
+    }
+
+    engine.stop_search();
+    engine.finish_search();
+    Ok(())
+}
+
+impl Engine {
+    fn new(tables: Tables) -> Self {
+        Engine {
+            board: None,
+            search: None,
+            last_best: None,
+            threads: 1,
+            tables,
+        }
+    }
+
+    // Runs one command line; returns false on quit
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            Some(command) => command,
+            None => return true,
+        };
+        let args: Vec<&str> = tokens.collect();
+        match command {
+            "quit" => return false,
+            "isready" => println!("readyok"),
+            "stop" => self.stop_search(),
+            _ if self.is_blocked() => println!("error search running"),
+            "bestmove" => {
+                // A running search reports its own result when it finishes
+                if !self.finish_search() {
+                    println!("bestmove {}", format_move(self.last_best));
+                }
+            }
+            command => {
+                // Everything else works on the position, so let any search finish first
+                self.finish_search();
+                if let Err(e) = self.execute(command, &args) {
+                    println!("error {}", e);
+                }
+            }
+        }
+        true
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        match command {
+            "position" => {
+                let board = match args {
+                    ["random", seed] => {
+                        Board::random(seed.parse().map_err(|_| format!("invalid seed '{}'", seed))?)
+                    }
+                    [notation] => Board::from_notation(notation).map_err(|e| e.to_string())?,
+                    _ => return Err("usage: position <notation> | position random <seed>".into()),
+                };
+                self.board = Some(board);
+                self.last_best = None;
+            }
+            "walls" => {
+                let board = self.board.as_mut().ok_or("no position set")?;
+                for arg in args {
+                    let p = parse_move(arg)?;
+                    if board.has_cat_escaped() {
+                        return Err(format!("cat already escaped, cannot play {}", arg));
+                    }
+                    if !board.move_player(p) {
+                        return Err(format!("illegal wall {}", arg));
+                    }
+                    if board.move_cat().is_none() {
+                        println!("info string cat trapped");
+                    }
+                }
+                self.last_best = None;
+            }
+            "go" => {
+                let board = self.board.clone().ok_or("no position set")?;
+                let budget = parse_budget(args)?;
+                self.start_search(board, budget);
+            }
+            "eval" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                match board.min_cat_moves() {
+                    N => println!("eval trapped"),
+                    n if board.has_cat_escaped() => println!("eval escaped min_cat_moves {}", n),
+                    n => {
+                        let escape = board.shortest_escape_paths(1).concat();
+                        let escape: Vec<String> = escape.into_iter().map(|p| format_move(Some(p))).collect();
+                        println!(
+                            "eval min_cat_moves {} paths {} escape {}",
+                            n,
+                            board.num_escape_paths(),
+                            escape.join(" ")
+                        );
+                    }
+                }
+            }
+            "field" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                let field = match args {
+                    ["distance"] => board.edge_distance_field().map(|d| d.map_or("#".to_string(), |d| d.to_string())),
+                    ["paths"] => board.escape_path_counts().map(|&n| if n == 0 { "#".to_string() } else { n.to_string() }),
+                    _ => return Err("usage: field <distance|paths>".into()),
+                };
+                print!("{}", field.display(&board.topology));
+            }
+            "heatmap" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                print!("{:#}", board);
+            }
+            "region" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                let region = board.cat_region();
+                let points: Vec<String> = region.articulation_points().into_iter().map(|p| format_move(Some(p))).collect();
+                print!(
+                    "region size {} edge {} articulation {}",
+                    region.size(),
+                    region.touches_edge,
+                    points.join(" ")
+                );
+                match args {
+                    [] => println!(),
+                    [k] => match k.parse::<usize>() {
+                        Ok(k) => println!(" enclosed {} {}", k, board.is_cat_enclosed_within(k)),
+                        Err(_) => return Err(format!("invalid cell count '{}'", k)),
+                    },
+                    _ => return Err("usage: region [k]".into()),
+                }
+            }
+            "setoption" => match args {
+                ["threads", n] => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => self.threads = n,
+                    _ => return Err(format!("invalid thread count '{}'", n)),
+                },
+                _ => return Err("usage: setoption threads <n>".into()),
+            },
+            "hex" => match args {
+                [cell] => {
+                    let a = Axial::from(parse_move(cell)?);
+                    let cube = a.cube();
+                    let edge = Topology::hex().exits().map(|p| a.distance(Axial::from(p))).min().unwrap_or(0);
+                    println!(
+                        "hex {} axial {},{} cube {},{},{} edge {}",
+                        cell, a.q, a.r, cube.x, cube.y, cube.z, edge
+                    );
+                }
+                ["ring", cell, k] => {
+                    let k = match k.parse::<usize>() {
+                        Ok(k) if k <= N => k,
+                        _ => return Err(format!("invalid radius '{}' (at most {})", k, N)),
+                    };
+                    let ring = Axial::from(parse_move(cell)?).ring(k);
+                    println!("ring {}", format_cells(ring));
+                }
+                ["line", from, to] => {
+                    let line = Axial::from(parse_move(from)?).line_to(Axial::from(parse_move(to)?));
+                    println!("line {}", format_cells(line));
+                }
+                ["rotate", cell, center, turns] => {
+                    let turns = turns.parse().map_err(|_| format!("invalid turn count '{}'", turns))?;
+                    let a = Axial::from(parse_move(cell)?).rotate(Axial::from(parse_move(center)?), turns);
+                    println!("rotate {}", format_cells(vec![a]));
+                }
+                _ => return Err("usage: hex <r,c> | hex <ring r,c k | line r,c r,c | rotate r,c r,c n>".into()),
+            },
+            "hash" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                println!(
+                    "hash {:016x} canonical {:016x} symmetry {}",
+                    board.zobrist_hash(),
+                    symmetry::canonical_hash(board),
+                    symmetry::canonical(board).1
+                );
+            }
+            "board" => {
+                let board = self.board.as_ref().ok_or("no position set")?;
+                print!("{}", board);
+                println!("{}", board.to_notation());
+            }
+            _ => return Err(format!("unknown command '{}'", command)),
+        }
+        Ok(())
+    }
+
+    fn start_search(&mut self, board: Board, budget: SearchBudget) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let mut player = self.tables.auto_player();
+        player.threads = self.threads;
+        let unlimited = budget.nodes.is_none() && budget.time.is_none() && budget.memory.is_none();
+        let handle = thread::spawn(move || {
+            let result = player.search(&board, budget, &thread_stop, &mut print_info);
+            match &result.solution {
+                Some(plan) => println!("info string proven win in {}", plan.len()),
+                None => println!("info string heuristic"),
+            }
+            println!("bestmove {}", format_move(result.best_move()));
+            result
+        });
+        self.search = Some(RunningSearch {
+            stop,
+            handle,
+            unlimited,
+        });
+    }
+
+    fn stop_search(&self) {
+        if let Some(search) = &self.search {
+            search.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Whether waiting for the running search could take forever: it has no
+    // limits, nothing has stopped it, and it is still going
+    fn is_blocked(&self) -> bool {
+        self.search
+            .as_ref()
+            .is_some_and(|s| s.unlimited && !s.stop.load(Ordering::Relaxed) && !s.handle.is_finished())
+    }
+
+    // Waits for the running search, if any. Returns whether there was one.
+    fn finish_search(&mut self) -> bool {
+        match self.search.take() {
+            Some(search) => {
+                if let Ok(result) = search.handle.join() {
+                    self.last_best = result.best_move();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn print_info(info: &SearchInfo) {
+    println!(
+        "info depth {} nodes {} time {} score {} best {}",
+        info.depth,
+        info.nodes,
+        info.elapsed.as_millis(),
+        info.best_score,
+        format_move(info.best_move)
+    );
+}
+
+fn parse_budget(args: &[&str]) -> Result<SearchBudget, String> {
+    let mut budget = SearchBudget::default();
+    let mut iter = args.iter();
+    while let Some(&arg) = iter.next() {
+        let mut value = || -> Result<u64, String> {
+            let v = iter.next().ok_or(format!("missing value for {}", arg))?;
+            v.parse().map_err(|_| format!("invalid value '{}' for {}", v, arg))
+        };
+        match arg {
+            "nodes" => budget.nodes = Some(value()? as usize),
+            "movetime" => budget.time = Some(Duration::from_millis(value()?)),
+            "memory" => {
+                let bytes = (value()? as usize).checked_mul(1024 * 1024);
+                budget.memory = Some(bytes.ok_or(format!("memory limit too large for {}", arg))?);
+            }
+            "infinite" => {}
+            _ => return Err(format!("unknown go option '{}'", arg)),
+        }
+    }
+    Ok(budget)
+}
+
+fn parse_move(s: &str) -> Result<Position, String> {
+    let (r, c) = s.split_once(',').ok_or(format!("invalid move '{}', expected r,c", s))?;
+    match (r.parse(), c.parse()) {
+        (Ok(r), Ok(c)) => Ok(Position::new(r, c)),
+        _ => Err(format!("invalid move '{}', expected r,c", s)),
+    }
+}
+
+// Cells on the board as moves, "none" if there are none
+fn format_cells(cells: Vec<Axial>) -> String {
+    let moves: Vec<String> = cells
+        .into_iter()
+        .map(Axial::to_position)
+        .filter(Position::is_valid)
+        .map(|p| format_move(Some(p)))
+        .collect();
+    if moves.is_empty() {
+        "none".to_string()
+    } else {
+        moves.join(" ")
+    }
+}
+
+fn format_move(p: Option<Position>) -> String {
+    p.map_or_else(|| "none".to_string(), |p| format!("{},{}", p.r, p.c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A search without limits must not block the commands that stop it
+    #[test]
+    fn infinite_search_stops_on_request() {
+        let mut engine = Engine::new(Tables::default());
+        assert!(engine.handle("position random 1"));
+        assert!(engine.handle("go infinite"));
+        assert!(engine.is_blocked() || engine.search.as_ref().unwrap().handle.is_finished());
+        assert!(engine.handle("bestmove")); // Answered with an error, not waited for
+        assert!(engine.handle("stop"));
+        assert!(!engine.is_blocked());
+        assert!(engine.handle("bestmove"));
+        assert!(engine.search.is_none());
+        assert!(engine.last_best.is_some());
+
+        assert!(parse_budget(&["memory", &u64::MAX.to_string()]).is_err());
+        assert!(engine.execute("hex", &["ring", "5,5", "1000000"]).is_err());
+        assert!(!engine.handle("quit"));
+    }
+}
@@ -0,0 +1,158 @@
+// --- The cat's region ---
+//
+// The empty cells the cat can still reach, found with the same BFS from the cat
+// as `min_cat_moves` but without stopping at the edge. Walls outside the
+// region can never matter, and a region that no longer touches the edge means
+// the cat is enclosed; the smaller the enclosure, the better the trap.
+
+use crate::field::Grid;
+use crate::topology::Topology;
+use crate::{Board, Position, N};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub cells: Vec<Position>, // In BFS order from the cat, so the cat's cell first
+    pub touches_edge: bool,
+    member: Grid<bool>,
+    topology: Arc<Topology>,
+}
+
+impl Region {
+    pub fn of(board: &Board) -> Self {
+        let mut member = Grid::new(false);
+        let mut cells = Vec::new();
+        let mut queue = VecDeque::with_capacity(N);
+        member[board.cat_position] = true;
+        queue.push_back(board.cat_position);
+        while let Some(p) = queue.pop_front() {
+            cells.push(p);
+            for &q in board.neighbors(p) {
+                if !board.cells[q.idx()] && !member[q] {
+                    member[q] = true;
+                    queue.push_back(q);
+                }
+            }
+        }
+
+        Region {
+            touches_edge: cells.iter().any(|&p| board.is_exit(p)),
+            cells,
+            member,
+            topology: board.topology.clone(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn contains(&self, p: Position) -> bool {
+        p.is_valid() && self.member[p]
+    }
+
+    // The cat cannot reach the edge and has at most `k` cells to move in,
+    // its own included
+    pub fn is_enclosed_within(&self, k: usize) -> bool {
+        !self.touches_edge && self.size() <= k
+    }
+
+    // Cells whose wall would split the region (the cat's own cell can be one),
+    // in board order. Tarjan's low-link DFS from the cat, kept iterative.
+    pub fn articulation_points(&self) -> Vec<Position> {
+        let root = self.cells[0];
+        let mut order = Grid::new(usize::MAX); // DFS discovery index
+        let mut low = Grid::new(usize::MAX);
+        let mut is_cut = Grid::new(false);
+        let mut root_children = 0;
+
+        // (cell, parent, neighbours still to visit)
+        let mut stack: Vec<(Position, Option<Position>, Vec<Position>)> = Vec::new();
+        let mut next = 0;
+        order[root] = next;
+        low[root] = next;
+        stack.push((root, None, self.neighbors(root)));
+
+        while let Some((p, parent, pending)) = stack.last_mut() {
+            let (p, parent) = (*p, *parent);
+            match pending.pop() {
+                Some(q) if order[q] == usize::MAX => {
+                    next += 1;
+                    order[q] = next;
+                    low[q] = next;
+                    if p == root {
+                        root_children += 1;
+                    }
+                    let neighbors = self.neighbors(q);
+                    stack.push((q, Some(p), neighbors));
+                }
+                Some(q) => {
+                    if Some(q) != parent {
+                        low[p] = low[p].min(order[q]);
+                    }
+                }
+                None => {
+                    stack.pop();
+                    if let Some(parent) = parent {
+                        low[parent] = low[parent].min(low[p]);
+                        if parent != root && low[p] >= order[parent] {
+                            is_cut[parent] = true;
+                        }
+                    }
+                }
+            }
+        }
+        if root_children > 1 {
+            is_cut[root] = true;
+        }
+
+        let mut points: Vec<Position> = self.cells.iter().copied().filter(|&p| is_cut[p]).collect();
+        points.sort();
+        points
+    }
+
+    fn neighbors(&self, p: Position) -> Vec<Position> {
+        self.topology
+            .neighbors(p)
+            .iter()
+            .copied()
+            .filter(|&q| self.member[q])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // A cell is an articulation point exactly when walling it leaves some of
+    // the rest of the region unreachable from another part
+    #[test]
+    fn articulation_points_split_the_region() {
+        let mut rng = Rng::new(7);
+        for seed in 0..200 {
+            let mut board = Board::random(seed);
+            for _ in 0..rng.below(50) {
+                let p = Position::from_idx(rng.below(N));
+                if p != board.cat_position {
+                    board.cells.set(p.idx(), true);
+                }
+            }
+            let region = Region::of(&board);
+            let points = region.articulation_points();
+            for &p in &region.cells {
+                // Count the region from any other cell with p walled
+                let Some(&start) = region.cells.iter().find(|&&q| q != p) else {
+                    continue;
+                };
+                let mut walled = board.clone();
+                walled.cells.set(p.idx(), true);
+                walled.cat_position = start;
+                let splits = Region::of(&walled).size() < region.size() - 1;
+                assert_eq!(points.contains(&p), splits, "seed {} at {}\n{}", seed, p, board);
+            }
+        }
+    }
+}
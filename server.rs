@@ -0,0 +1,364 @@
+// --- Local HTTP JSON API (feature "server") ---
+//
+// A deliberately small HTTP/1.1 server on top of std::net so the web front-end
+// can use the engine instead of its built-in solver. One request per
+// connection, requests handled one at a time.
+//
+//   POST /games              body: board text      -> new game
+//   POST /games?seed=N                             -> new game on a random board
+//   GET  /games/{id}                               -> game state
+//   POST /games/{id}/walls?r=R&c=C                 -> place a wall, cat answers
+//   GET  /games/{id}/hint                          -> suggested wall, proven or heuristic
+//   GET  /games/{id}/eval                          -> position evaluation
+//   GET  /games/{id}/record                        -> game record
+//   POST /move-cat           body: board text      -> cat's reply from move_cat
+
+use crate::game::{Game, GameRecord, MoveError};
+use crate::strategies::Tables;
+use crate::{Board, Position, N};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::AtomicBool;
+
+// Largest request body accepted; boards are a few hundred bytes
+const MAX_BODY: usize = 64 * 1024;
+
+struct Request {
+    method: String,
+    path: Vec<String>,
+    query: HashMap<String, String>,
+    body: String,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            body: format!("{{\"error\":{}}}", json_string(message)),
+        }
+    }
+}
+
+pub struct Server {
+    games: HashMap<u64, Game>,
+    next_id: u64,
+    tables: Tables, // For the hints' searches
+}
+
+impl Server {
+    pub fn new(tables: Tables) -> Self {
+        Server {
+            games: HashMap::new(),
+            next_id: 1,
+            tables,
+        }
+    }
+
+    pub fn run(&mut self, addr: &str) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        eprintln!("Listening on http://{}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = self.handle_connection(stream) {
+                        eprintln!("Connection error: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Accept error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        let response = match read_request(&mut stream)? {
+            Ok(request) => self.route(&request),
+            Err(response) => response,
+        };
+        let reason = match response.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            _ => "Error",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\r\n{}",
+            response.status,
+            reason,
+            response.body.len(),
+            response.body
+        )?;
+        stream.flush()
+    }
+
+    fn route(&mut self, request: &Request) -> Response {
+        let path: Vec<&str> = request.path.iter().map(String::as_str).collect();
+        match (request.method.as_str(), path.as_slice()) {
+            ("OPTIONS", _) => Response {
+                status: 204,
+                body: String::new(),
+            },
+            ("POST", ["games"]) => self.create_game(request),
+            ("POST", ["move-cat"]) => match parse_board(&request.body) {
+                Ok(mut board) => {
+                    let cat = board.move_cat();
+                    Response::ok(format!(
+                        "{{\"cat\":{},\"board\":{}}}",
+                        option_position_json(cat),
+                        board_json(&board)
+                    ))
+                }
+                Err(e) => Response::error(400, &e),
+            },
+            (method, ["games", id, rest @ ..]) => {
+                let game = match id.parse::<u64>().ok().and_then(|id| self.games.get_mut(&id)) {
+                    Some(game) => game,
+                    None => return Response::error(404, "No such game"),
+                };
+                match (method, rest) {
+                    ("GET", []) => Response::ok(game_json(game)),
+                    ("POST", ["walls"]) => play_wall(game, request),
+                    ("GET", ["hint"]) => {
+                        let player = self.tables.auto_player();
+                        let result = player.search(&game.board, player.budget, &AtomicBool::new(false), &mut |_| {});
+                        match result.best_move() {
+                            Some(mv) => Response::ok(format!(
+                                "{{\"move\":{},\"proven\":{}}}",
+                                position_json(mv),
+                                result.is_proven()
+                            )),
+                            None => Response::error(409, "No wall can be placed"),
+                        }
+                    }
+                    ("GET", ["eval"]) => Response::ok(eval_json(&game.board)),
+                    ("GET", ["record"]) => Response::ok(record_json(&game.record)),
+                    _ => Response::error(404, "Not found"),
+                }
+            }
+            _ => Response::error(404, "Not found"),
+        }
+    }
+
+    fn create_game(&mut self, request: &Request) -> Response {
+        let board = match request.query.get("seed") {
+            Some(seed) => match seed.parse::<u64>() {
+                Ok(seed) => Board::random(seed),
+                Err(_) => return Response::error(400, "Invalid seed"),
+            },
+            None => match parse_board(&request.body) {
+                Ok(board) => board,
+                Err(e) => return Response::error(400, &e),
+            },
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        let game = Game::new(board);
+        let body = format!("{{\"id\":{},\"game\":{}}}", id, game_json(&game));
+        self.games.insert(id, game);
+        Response::ok(body)
+    }
+}
+
+fn play_wall(game: &mut Game, request: &Request) -> Response {
+    let coord = |key: &str| request.query.get(key).and_then(|v| v.parse().ok());
+    let p = match (coord("r"), coord("c")) {
+        (Some(r), Some(c)) => Position::new(r, c),
+        _ => return Response::error(400, "Expected query parameters r and c"),
+    };
+    match game.play_wall(p) {
+        Ok(cat) => Response::ok(format!(
+            "{{\"cat\":{},\"game\":{}}}",
+            option_position_json(cat),
+            game_json(game)
+        )),
+        Err(e @ MoveError::GameOver(_)) => Response::error(409, &e.to_string()),
+        Err(e) => Response::error(400, &e.to_string()),
+    }
+}
+
+// Reads the request line, headers and a Content-Length body. Returns the
+// error response instead if the request line is malformed or the body is
+// larger than MAX_BODY.
+fn read_request(stream: &mut TcpStream) -> io::Result<Result<Request, Response>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Ok(Err(Response::error(400, "Malformed request"))),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Ok(Err(Response::error(413, "Request body too large")));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    Ok(Ok(Request {
+        method,
+        path: path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        query: query
+            .split('&')
+            .filter_map(|kv| kv.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+fn parse_board(text: &str) -> Result<Board, String> {
+    Board::from_reader(&mut text.as_bytes()).map_err(|e| e.to_string())
+}
+
+// --- JSON encoding ---
+
+fn json_string(s: &str) -> String {
+    let mut ret = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn position_json(p: Position) -> String {
+    format!("{{\"r\":{},\"c\":{}}}", p.r, p.c)
+}
+
+fn option_position_json(p: Option<Position>) -> String {
+    p.map_or_else(|| "null".to_string(), position_json)
+}
+
+fn board_json(board: &Board) -> String {
+    let rows: Vec<String> = board.to_string().lines().map(json_string).collect();
+    format!(
+        "{{\"cat\":{},\"rows\":[{}]}}",
+        position_json(board.cat_position),
+        rows.join(",")
+    )
+}
+
+fn game_json(game: &Game) -> String {
+    format!(
+        "{{\"status\":\"{}\",\"walls\":{},\"board\":{}}}",
+        game.status(),
+        game.record.plies.len(),
+        board_json(&game.board)
+    )
+}
+
+fn eval_json(board: &Board) -> String {
+    let min_cat_moves = board.min_cat_moves();
+    if min_cat_moves == N {
+        "{\"trapped\":true,\"min_cat_moves\":null}".to_string()
+    } else {
+        format!("{{\"trapped\":false,\"min_cat_moves\":{}}}", min_cat_moves)
+    }
+}
+
+fn record_json(record: &GameRecord) -> String {
+    let plies: Vec<String> = record
+        .plies
+        .iter()
+        .map(|ply| {
+            format!(
+                "{{\"wall\":{},\"cat\":{}}}",
+                position_json(ply.wall),
+                option_position_json(ply.cat)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"initial\":{},\"plies\":[{}],\"text\":{}}}",
+        board_json(&record.initial),
+        plies.join(","),
+        json_string(&record.to_string())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // Sends one raw request and returns the status code and body
+    fn send(addr: std::net::SocketAddr, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+        (status, body)
+    }
+
+    #[test]
+    fn server_plays_over_loopback_and_caps_bodies() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = 5;
+        let server = thread::spawn(move || {
+            let mut server = Server::new(Tables::default());
+            for _ in 0..requests {
+                server.handle_connection(listener.accept().unwrap().0).unwrap();
+            }
+        });
+
+        let (status, body) = send(addr, "POST /games?seed=3 HTTP/1.1\r\n\r\n");
+        assert_eq!(status, 200);
+        assert!(body.starts_with("{\"id\":1,"), "{}", body);
+        let (status, body) = send(addr, "POST /games/1/walls?r=0&c=0 HTTP/1.1\r\n\r\n");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"walls\":1"), "{}", body);
+        assert_eq!(send(addr, "GET /games/2 HTTP/1.1\r\n\r\n").0, 404);
+        let huge = "POST /move-cat HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n";
+        assert_eq!(send(addr, huge).0, 413);
+        assert_eq!(send(addr, "\r\n").0, 400);
+        server.join().unwrap();
+    }
+}
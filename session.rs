@@ -0,0 +1,276 @@
+// --- Game sessions ---
+//
+// A session plays one game between two seats: humans at this terminal or remote
+// clients over TCP. Every move arrives as a line of text and is
+// validated here through `Game` (which uses `Board::move_player` and
+// `Board::move_cat_to`), so remote clients cannot cheat.
+//
+// Line protocol, server to client:
+//   side <walls|cat>...      the side(s) this seat plays, sent once
+//   board <notation>         the position, sent before each move request
+//   turn <walls|cat>         asks for a move
+//   error <message>          the move was rejected, send another one
+//   moved <walls|cat> <r> <c>
+//   gameover <walls|cat>     the winning side
+// Client to server:
+//   <r> <c>                  a wall, or the cat's target cell
+//   a <q> <r> | <x> <y> <z>  the same cell in axial or cube coordinates
+//   <direction>              the cat's direction (w, nw, ne, e, se, sw)
+// At the terminal the walls can also enter `hint [k]` for suggested walls.
+
+use crate::game::{Game, GameStatus, Side};
+use crate::{hex, hints};
+use crate::{Board, Int, Position};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+pub trait Seat {
+    // Asks for the next move of `side` as a line of text
+    fn request_move(&mut self, game: &Game, side: Side) -> io::Result<String>;
+    // Passes on a protocol message
+    fn notify(&mut self, message: &str) -> io::Result<()>;
+}
+
+pub struct Session {
+    game: Game,
+    seats: Vec<Box<dyn Seat>>,
+    walls: usize, // Index into seats
+    cat: usize,
+}
+
+impl Session {
+    pub fn new(board: Board, walls: Box<dyn Seat>, cat: Box<dyn Seat>) -> Self {
+        Session {
+            game: Game::new(board),
+            seats: vec![walls, cat],
+            walls: 0,
+            cat: 1,
+        }
+    }
+
+    // Both sides played from the same seat (hot-seat play)
+    pub fn shared(board: Board, seat: Box<dyn Seat>) -> Self {
+        Session {
+            game: Game::new(board),
+            seats: vec![seat],
+            walls: 0,
+            cat: 0,
+        }
+    }
+
+    fn seat_index(&self, side: Side) -> usize {
+        match side {
+            Side::Walls => self.walls,
+            Side::Cat => self.cat,
+        }
+    }
+
+    fn broadcast(&mut self, message: &str) -> io::Result<()> {
+        for seat in &mut self.seats {
+            seat.notify(message)?;
+        }
+        Ok(())
+    }
+
+    // Plays until the game is over and returns the final status
+    pub fn run(&mut self) -> io::Result<GameStatus> {
+        for (i, seat) in self.seats.iter_mut().enumerate() {
+            let mut sides = Vec::new();
+            if i == self.walls {
+                sides.push("walls");
+            }
+            if i == self.cat {
+                sides.push("cat");
+            }
+            seat.notify(&format!("side {}", sides.join(" ")))?;
+        }
+
+        loop {
+            let status = self.game.status();
+            if status != GameStatus::Playing {
+                let winner = if status == GameStatus::Won { Side::Walls } else { Side::Cat };
+                self.broadcast(&format!("gameover {}", winner))?;
+                return Ok(status);
+            }
+
+            let side = self.game.turn;
+            let index = self.seat_index(side);
+            let line = self.seats[index].request_move(&self.game, side)?;
+            match self.apply(side, &line) {
+                Ok(p) => self.broadcast(&format!("moved {} {} {}", side, p.r, p.c))?,
+                Err(e) => self.seats[index].notify(&format!("error {}", e))?,
+            }
+        }
+    }
+
+    fn apply(&mut self, side: Side, line: &str) -> Result<Position, String> {
+        let p = parse_move(&self.game.board, side, line)?;
+        match side {
+            Side::Walls => self.game.place_wall(p),
+            Side::Cat => self.game.move_cat_to(p),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(p)
+    }
+}
+
+fn parse_move(board: &Board, side: Side, line: &str) -> Result<Position, String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if let Some(cell) = hex::parse_cell(&parts) {
+        return cell;
+    }
+    match parts.as_slice() {
+        [r, c] => match (r.parse::<Int>(), c.parse::<Int>()) {
+            (Ok(r), Ok(c)) => Ok(Position::new(r, c)),
+            _ => Err(format!("Invalid move '{}'", line.trim())),
+        },
+        [dir] if side == Side::Cat => board.step_towards(board.cat_position, dir),
+        _ if side == Side::Cat => Err("Expected a direction or 'r c'".to_string()),
+        _ => Err("Expected 'r c'".to_string()),
+    }
+}
+
+// Minimal client for `join`: relays stdin to the server and the server's
+// messages to stdout until the game is over
+pub fn connect(addr: &str) -> io::Result<()> {
+    let stream = TcpStream::connect(addr)?;
+    let mut writer = stream.try_clone()?;
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) if writeln!(writer, "{}", line).is_ok() => {}
+                _ => break,
+            }
+        }
+    });
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        println!("{}", line);
+        if line.starts_with("gameover") {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// --- Seats ---
+
+// A human at this terminal
+pub struct TerminalSeat;
+
+impl Seat for TerminalSeat {
+    fn request_move(&mut self, game: &Game, side: Side) -> io::Result<String> {
+        println!("{}", game.board);
+        match side {
+            Side::Walls => println!("Walls, please enter your move (r c), or 'hint [k]' for suggestions:"),
+            Side::Cat => println!("Cat, please enter your direction (w, nw, ne, e, se, sw):"),
+        }
+        loop {
+            io::stdout().flush()?;
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stdin closed"));
+            }
+            match hints::parse_hint_command(&input) {
+                Some(k) if side == Side::Walls => hints::print_hints(&game.board, k),
+                _ => return Ok(input),
+            }
+        }
+    }
+
+    fn notify(&mut self, message: &str) -> io::Result<()> {
+        println!("{}", message);
+        Ok(())
+    }
+}
+
+// A remote client speaking the line protocol over TCP
+pub struct TcpSeat {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl TcpSeat {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(TcpSeat {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+}
+
+impl Seat for TcpSeat {
+    fn request_move(&mut self, game: &Game, side: Side) -> io::Result<String> {
+        writeln!(self.writer, "board {}", game.board.to_notation())?;
+        writeln!(self.writer, "turn {}", side)?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "client disconnected"));
+        }
+        Ok(line)
+    }
+
+    fn notify(&mut self, message: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn read_until(reader: &mut impl BufRead, prefix: &str) -> String {
+        loop {
+            let mut line = String::new();
+            assert!(reader.read_line(&mut line).unwrap() > 0, "connection closed");
+            if line.starts_with(prefix) {
+                return line.trim().to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn tcp_session_validates_moves() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut board = Board::new();
+        board.cat_position = Position::new(1, 2);
+        let server = thread::spawn(move || {
+            let walls = TcpSeat::new(listener.accept().unwrap().0).unwrap();
+            let cat = TcpSeat::new(listener.accept().unwrap().0).unwrap();
+            Session::new(board, Box::new(walls), Box::new(cat)).run().unwrap()
+        });
+
+        let mut walls = TcpStream::connect(addr).unwrap();
+        let mut walls_reader = BufReader::new(walls.try_clone().unwrap());
+        let mut cat = TcpStream::connect(addr).unwrap();
+        let mut cat_reader = BufReader::new(cat.try_clone().unwrap());
+        assert_eq!(read_until(&mut walls_reader, "side"), "side walls");
+        assert_eq!(read_until(&mut cat_reader, "side"), "side cat");
+
+        // Walls cannot build on the cat, then build west of it
+        read_until(&mut walls_reader, "turn");
+        writeln!(walls, "1 2").unwrap();
+        assert!(read_until(&mut walls_reader, "error").contains("Invalid move"));
+        read_until(&mut walls_reader, "turn");
+        writeln!(walls, "1 1").unwrap();
+        assert_eq!(read_until(&mut walls_reader, "moved"), "moved walls 1 1");
+
+        // The cat cannot step onto the wall or jump, then escapes north-west
+        read_until(&mut cat_reader, "turn");
+        writeln!(cat, "w").unwrap();
+        assert!(read_until(&mut cat_reader, "error").contains("wall"));
+        read_until(&mut cat_reader, "turn");
+        writeln!(cat, "3 3").unwrap();
+        assert!(read_until(&mut cat_reader, "error").contains("one step"));
+        read_until(&mut cat_reader, "turn");
+        writeln!(cat, "nw").unwrap();
+        assert_eq!(read_until(&mut cat_reader, "moved"), "moved cat 0 2");
+
+        assert_eq!(read_until(&mut walls_reader, "gameover"), "gameover cat");
+        assert_eq!(server.join().unwrap(), GameStatus::Lost);
+    }
+}
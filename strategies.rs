@@ -0,0 +1,257 @@
+// --- Additional wall and cat strategies ---
+// Cheap alternatives to AutoPlayer and the built-in cat, selectable by name on
+// the command line.
+
+use crate::alphabeta::{AlphaBetaConfig, AlphaBetaPlayer};
+use crate::book::{Book, BookPlayer};
+use crate::mcts::{MctsConfig, MctsPlayer};
+use crate::rng::Rng;
+use crate::tablebase::Tablebase;
+use crate::{AutoPlayer, Board, CatPlayer, DefaultCat, Player, Position, C, N, R};
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+// Names accepted by `wall_player`; "book" only with a book in the tables
+pub const WALL_STRATEGIES: [&str; 6] = ["auto", "book", "greedy", "random", "mcts", "alphabeta"];
+
+// Precomputed tables the players search with, loaded once from the files
+// named on the command line and shared by every player built from them
+#[derive(Debug, Clone, Default)]
+pub struct Tables {
+    pub tablebase: Option<Arc<Tablebase>>,
+    pub book: Option<Arc<Book>>,
+}
+
+impl Tables {
+    // An AutoPlayer with the default budget that probes these tables
+    pub fn auto_player(&self) -> AutoPlayer {
+        AutoPlayer {
+            tablebase: self.tablebase.clone(),
+            ..AutoPlayer::new()
+        }
+    }
+}
+
+pub fn wall_player(name: &str, seed: u64, tables: &Tables) -> Option<Box<dyn Player>> {
+    match name {
+        "auto" => Some(Box::new(tables.auto_player())),
+        "book" => Some(Box::new(BookPlayer::new(tables.book.clone()?, tables.auto_player()))),
+        "greedy" => Some(Box::new(GreedyPlayer)),
+        "random" => Some(Box::new(RandomPlayer::new(seed))),
+        "mcts" => Some(Box::new(MctsPlayer::new(
+            MctsConfig {
+                seed,
+                ..MctsConfig::default()
+            },
+            Box::new(DefaultCat),
+        ))),
+        "alphabeta" => Some(Box::new(AlphaBetaPlayer::new(AlphaBetaConfig::default()))),
+        _ => None,
+    }
+}
+
+// Why `wall_player` (or `wall_policy`, given a policy spec) has no player
+pub fn wall_strategy_error(spec: &str, tables: &Tables) -> String {
+    if spec.split(':').next() == Some("book") && tables.book.is_none() {
+        "The book strategy needs an opening book, given with --book <file>".to_string()
+    } else {
+        format!("Unknown wall strategy '{}'", spec)
+    }
+}
+
+// Empty cells a wall can be placed on
+pub fn legal_walls(board: &Board) -> Vec<Position> {
+    let mut ret = Vec::new();
+    for r in 0..R {
+        for c in 0..C {
+            let p = Position::new(r, c);
+            if !board.cells[p.idx()] && p != board.cat_position {
+                ret.push(p);
+            }
+        }
+    }
+    ret
+}
+
+// One-ply lookahead: picks the wall after which the cat's best reply leaves
+// it furthest from the edge, blocking the cat's next step on ties
+pub struct GreedyPlayer;
+
+impl GreedyPlayer {
+    // Higher is better for the player; N means the wall traps the cat
+    fn score(board: &Board, p: Position) -> usize {
+        let mut next = board.clone();
+        next.cells.set(p.idx(), true);
+        if next.move_cat().is_none() {
+            return N;
+        }
+        if next.has_cat_escaped() {
+            return 0;
+        }
+        next.min_cat_moves()
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let cat_move = board.best_cat_move();
+        legal_walls(board)
+            .into_iter()
+            .max_by_key(|&p| (GreedyPlayer::score(board, p), Some(p) == cat_move, Reverse(p)))
+    }
+}
+
+// Uniformly random legal wall, reproducible from its seed
+pub struct RandomPlayer {
+    rng: Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        RandomPlayer { rng: Rng::new(seed) }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let walls = legal_walls(board);
+        if walls.is_empty() {
+            return None;
+        }
+        Some(walls[self.rng.below(walls.len())])
+    }
+}
+
+// Names accepted by `cat_player`
+pub const CAT_STRATEGIES: [&str; 3] = ["default", "nearest", "random"];
+
+pub fn cat_player(name: &str, seed: u64) -> Option<Box<dyn CatPlayer>> {
+    match name {
+        "default" => Some(Box::new(DefaultCat)),
+        "nearest" => Some(Box::new(NearestCat)),
+        "random" => Some(Box::new(RandomCat::new(seed))),
+        _ => None,
+    }
+}
+
+// Empty cells next to the cat
+pub fn legal_cat_moves(board: &Board) -> Vec<Position> {
+    board
+        .neighbors(board.cat_position)
+        .iter()
+        .copied()
+        .filter(|p| !board.cells[p.idx()])
+        .collect()
+}
+
+// Takes the first direction that gets closer to the edge, like the web
+// front-end's `nearestSolver`, without weighing the number of paths
+pub struct NearestCat;
+
+impl CatPlayer for NearestCat {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let distance = board.min_cat_moves();
+        legal_cat_moves(board).into_iter().find(|&p| {
+            let mut next = board.clone();
+            next.cat_position = p;
+            next.min_cat_moves() < distance
+        })
+    }
+}
+
+// Uniformly random step, reproducible from its seed
+pub struct RandomCat {
+    rng: Rng,
+}
+
+impl RandomCat {
+    pub fn new(seed: u64) -> Self {
+        RandomCat { rng: Rng::new(seed) }
+    }
+}
+
+impl CatPlayer for RandomCat {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let moves = legal_cat_moves(board);
+        if moves.is_empty() {
+            return None;
+        }
+        Some(moves[self.rng.below(moves.len())])
+    }
+}
+
+// A strategy name with an optional noise level, "name" or "name:p": with
+// probability p the player makes a uniformly random legal move instead, so
+// deterministic strategies can be sampled. Returns the name and the noise.
+fn parse_policy(spec: &str) -> Option<(&str, f64)> {
+    match spec.split_once(':') {
+        None => Some((spec, 0.0)),
+        Some((name, noise)) => {
+            let noise: f64 = noise.parse().ok()?;
+            (0.0..=1.0).contains(&noise).then_some((name, noise))
+        }
+    }
+}
+
+// A wall player from a policy spec (see `parse_policy`)
+pub fn wall_policy(spec: &str, seed: u64, tables: &Tables) -> Option<Box<dyn Player>> {
+    let (name, noise) = parse_policy(spec)?;
+    let player = wall_player(name, seed, tables)?;
+    if noise == 0.0 {
+        return Some(player);
+    }
+    Some(Box::new(NoisyPlayer {
+        player,
+        noise,
+        rng: Rng::new(seed ^ NOISE_SEED),
+    }))
+}
+
+// A cat player from a policy spec (see `parse_policy`)
+pub fn cat_policy(spec: &str, seed: u64) -> Option<Box<dyn CatPlayer>> {
+    let (name, noise) = parse_policy(spec)?;
+    let cat = cat_player(name, seed)?;
+    if noise == 0.0 {
+        return Some(cat);
+    }
+    Some(Box::new(NoisyCat {
+        cat,
+        noise,
+        rng: Rng::new(seed ^ NOISE_SEED),
+    }))
+}
+
+// Keeps the noise stream apart from the wrapped player's own seeded choices
+const NOISE_SEED: u64 = 0x6e015e;
+
+struct NoisyPlayer {
+    player: Box<dyn Player>,
+    noise: f64,
+    rng: Rng,
+}
+
+impl Player for NoisyPlayer {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let walls = legal_walls(board);
+        if !walls.is_empty() && self.rng.next_f64() < self.noise {
+            return Some(walls[self.rng.below(walls.len())]);
+        }
+        self.player.get_move(board)
+    }
+}
+
+struct NoisyCat {
+    cat: Box<dyn CatPlayer>,
+    noise: f64,
+    rng: Rng,
+}
+
+impl CatPlayer for NoisyCat {
+    fn get_move(&mut self, board: &Board) -> Option<Position> {
+        let moves = legal_cat_moves(board);
+        if !moves.is_empty() && self.rng.next_f64() < self.noise {
+            return Some(moves[self.rng.below(moves.len())]);
+        }
+        self.cat.get_move(board)
+    }
+}
@@ -0,0 +1,163 @@
+// --- Board symmetries ---
+//
+// The symmetries of the board are the permutations of its cells, taken from
+// the twelve rotations and reflections of the hex grid, that map the board
+// onto itself. Odd rows are shifted right, so reversing the columns is not one
+// of them; turning the board upside down is, since the 11 rows keep their
+// parity. They are found by brute force rather than listed, and kept only if
+// they also map neighbours to neighbours and exits to exits, so they stay right
+// if the board's size changes. They are those of the plain hex grid: a board
+// with another topology is only ever its own canonical image.
+//
+// Symmetric positions are the same game against any cat that ignores
+// directions. `move_cat` breaks ties in Direction order, which a mirror
+// changes, so the search's own transposition table keeps them apart.
+
+use crate::field::Grid;
+use crate::hex::Axial;
+use crate::topology::{Kind, Topology};
+use crate::{Board, Position, N};
+use once_cell::sync::Lazy;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone)]
+pub struct Symmetry {
+    transform: usize, // Which of the 12, as in Axial::transform
+    map: Grid<Position>,
+    inverse: Grid<Position>,
+}
+
+impl Symmetry {
+    // Hex symmetry `t` followed by the translation that brings the board back
+    // onto itself, if there is one
+    fn find(t: usize, topology: &Topology) -> Option<Symmetry> {
+        let cells: Vec<Position> = (0..N).map(Position::from_idx).collect();
+        let moved: Vec<Axial> = cells.iter().map(|&p| Axial::from(p).transform(t)).collect();
+        // Line up the first cells in row order
+        let first = moved.iter().copied().min_by_key(|a| (a.r, a.q))?;
+        let shift = Axial::from(Position::new(0, 0)) - first;
+
+        let mut map = Grid::new(Position::new(0, 0));
+        let mut inverse = Grid::new(Position::new(0, 0));
+        for (&p, &a) in cells.iter().zip(&moved) {
+            let image = (a + shift).to_position();
+            if !image.is_valid() {
+                return None;
+            }
+            map[p] = image;
+            inverse[image] = p;
+        }
+        for &p in &cells {
+            let mut expected: Vec<Position> = topology.neighbors(p).iter().map(|&q| map[q]).collect();
+            let mut actual = topology.neighbors(map[p]).to_vec();
+            expected.sort();
+            actual.sort();
+            if actual != expected || topology.is_exit(map[p]) != topology.is_exit(p) {
+                return None;
+            }
+        }
+        Some(Symmetry {
+            transform: t,
+            map,
+            inverse,
+        })
+    }
+
+    pub fn apply(&self, p: Position) -> Position {
+        self.map[p]
+    }
+
+    // The cell that `apply` maps onto `p`
+    pub fn undo(&self, p: Position) -> Position {
+        self.inverse[p]
+    }
+
+    pub fn apply_board(&self, board: &Board) -> Board {
+        let mut image = board.clone();
+        image.cells.fill(false);
+        for i in board.cells.iter_ones() {
+            image.cells.set(self.apply(Position::from_idx(i)).idx(), true);
+        }
+        image.cat_position = self.apply(board.cat_position);
+        image
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.transform {
+            0 => write!(f, "identity"),
+            t if t < 6 => write!(f, "rotation by {} degrees", 60 * t),
+            t => write!(f, "reflection {}", t - 6),
+        }
+    }
+}
+
+// Every symmetry of the hex board, the identity first
+pub static SYMMETRIES: Lazy<Vec<Symmetry>> = Lazy::new(|| {
+    let hex = Topology::hex();
+    (0..12).filter_map(|t| Symmetry::find(t, &hex)).collect()
+});
+
+// The symmetric image of `board` with the smallest Zobrist hash, and the
+// symmetry that gives it; walls found on the image map back with `undo`
+pub fn canonical(board: &Board) -> (Board, &'static Symmetry) {
+    let count = if board.topology.kind() == Kind::Hex {
+        SYMMETRIES.len()
+    } else {
+        1
+    };
+    SYMMETRIES[..count]
+        .iter()
+        .map(|symmetry| (symmetry.apply_board(board), symmetry))
+        .min_by_key(|(image, _)| image.zobrist_hash())
+        .expect("the identity is a symmetry")
+}
+
+// Zobrist hash shared by all the symmetric images of `board`
+pub fn canonical_hash(board: &Board) -> u64 {
+    canonical(board).0.zobrist_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::R;
+
+    // The only symmetries are the identity and the flip between the top and
+    // bottom rows, and they keep neighbours and edges
+    #[test]
+    fn symmetries_are_the_vertical_flip() {
+        assert_eq!(SYMMETRIES.len(), 2);
+        assert_eq!(SYMMETRIES[0].to_string(), "identity");
+        for p in (0..N).map(Position::from_idx) {
+            let flipped = SYMMETRIES[1].apply(p);
+            assert_eq!(flipped, Position::new(R - 1 - p.r, p.c));
+            assert_eq!(SYMMETRIES[1].undo(flipped), p);
+        }
+        let hex = Topology::hex();
+        for symmetry in SYMMETRIES.iter() {
+            for p in (0..N).map(Position::from_idx) {
+                let mut expected: Vec<Position> = hex.neighbors(p).iter().map(|&q| symmetry.apply(q)).collect();
+                let mut actual = hex.neighbors(symmetry.apply(p)).to_vec();
+                expected.sort();
+                actual.sort();
+                assert_eq!(actual, expected, "{} at {}", symmetry, p);
+                assert_eq!(hex.is_exit(symmetry.apply(p)), hex.is_exit(p));
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_hash_is_shared_by_images() {
+        for seed in 0..20 {
+            let board = Board::random(seed);
+            let hash = canonical_hash(&board);
+            for symmetry in SYMMETRIES.iter() {
+                let image = symmetry.apply_board(&board);
+                assert_eq!(canonical_hash(&image), hash, "seed {} {}", seed, symmetry);
+                assert_eq!(image.min_cat_moves(), board.min_cat_moves());
+            }
+        }
+    }
+}
@@ -0,0 +1,520 @@
+// --- Endgame tablebase ---
+//
+// Once walls have confined the cat to a small region that still reaches the
+// edge, the rest of the game depends only on the region's shape, which of its
+// cells are on the edge, and where the cat stands in it. (A region cut off
+// from the edge is already a win, so those need no table.) The generator
+// solves every such region of up to `max_size` cells that fits on the board,
+// with the walls to move, against a cat that picks its replies to last as long
+// as possible. Each region is stored once for all the hex grid's rotations and
+// reflections, keyed in axial coordinates, and only wins are kept, so a
+// position within the table's size that is missing from it is lost against a
+// good cat. Since the values hold against any cat, the AutoPlayer search can
+// stop at a table hit and hand `move_cat` the table's line.
+//
+// An AutoPlayer probes the table it was given, if any, on boards with the
+// plain hex topology; on the command line that is the one loaded with
+// `--tablebase <file>`, and `tablebase <file> [max_size]` writes one.
+
+use crate::hex::Axial;
+use crate::plan::Plan;
+use crate::topology::{Kind, Topology};
+use crate::{Board, Direction, Position, N};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// Largest region a table can hold: normalised regions fit in an 8x8 square of
+// axial coordinates, one bit per cell
+pub const MAX_SIZE: usize = 8;
+const SPAN: i32 = 8;
+
+const MAGIC: &[u8; 6] = b"TTCTB\x01";
+
+// Cell of the 8x8 axial square for a bit, and back
+fn square_cell(bit: usize) -> Axial {
+    Axial::new(bit as i32 % SPAN, bit as i32 / SPAN)
+}
+
+fn square_bit(a: Axial) -> u64 {
+    1 << (a.r * SPAN + a.q)
+}
+
+// Neighbours of each cell of the 8x8 axial square, as bit masks
+static SQUARE_NEIGHBORS: Lazy<[u64; 64]> = Lazy::new(|| {
+    std::array::from_fn(|bit| {
+        Direction::ALL
+            .iter()
+            .map(|&d| square_cell(bit).step(d))
+            .filter(|a| (0..SPAN).contains(&a.q) && (0..SPAN).contains(&a.r))
+            .fold(0, |mask, a| mask | square_bit(a))
+    })
+});
+
+fn bits(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let bit = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            bit
+        })
+    })
+}
+
+// A region with the cat in it, in the canonical orientation: the smallest key
+// over all symmetries, each translated into the corner of the 8x8 square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Key {
+    cells: u64,
+    edge: u64, // Cells on the board's edge
+    cat: u8,   // Bit of the cat's cell
+}
+
+impl Key {
+    // `cells` are axial coordinates with their edge flags
+    fn new(cells: &[(Axial, bool)], cat: Axial) -> Key {
+        (0..12)
+            .map(|t| {
+                let moved: Vec<(Axial, bool)> = cells.iter().map(|&(a, edge)| (a.transform(t), edge)).collect();
+                let corner = Axial::new(
+                    moved.iter().map(|&(a, _)| a.q).min().unwrap_or(0),
+                    moved.iter().map(|&(a, _)| a.r).min().unwrap_or(0),
+                );
+                let bit = |a: Axial| square_bit(a - corner);
+                let mut key = Key {
+                    cells: 0,
+                    edge: 0,
+                    cat: bit(cat.transform(t)).trailing_zeros() as u8,
+                };
+                for &(a, edge) in &moved {
+                    key.cells |= bit(a);
+                    if edge {
+                        key.edge |= bit(a);
+                    }
+                }
+                key
+            })
+            .min()
+            .expect("12 symmetries")
+    }
+
+    // The cat's region on `board`, if it is small enough to be keyed
+    fn of_board(board: &Board) -> Option<Key> {
+        let region = board.cat_region();
+        if region.size() > MAX_SIZE {
+            return None;
+        }
+        let cells: Vec<(Axial, bool)> = region
+            .cells
+            .iter()
+            .map(|&p| (Axial::from(p), board.is_exit(p)))
+            .collect();
+        Some(Key::new(&cells, Axial::from(board.cat_position)))
+    }
+
+    // The same region with `cells` as the open ones and the cat on `cat`,
+    // canonicalised again
+    fn with(&self, cells: u64, cat: usize) -> Key {
+        let list: Vec<(Axial, bool)> = bits(cells).map(|b| (square_cell(b), self.edge >> b & 1 != 0)).collect();
+        Key::new(&list, square_cell(cat))
+    }
+
+    fn to_bytes(self) -> [u8; 17] {
+        let mut bytes = [0; 17];
+        bytes[..8].copy_from_slice(&self.cells.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.edge.to_le_bytes());
+        bytes[16] = self.cat;
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; 17]) -> Key {
+        Key {
+            cells: u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes")),
+            edge: u64::from_le_bytes(bytes[8..16].try_into().expect("8 bytes")),
+            cat: bytes[16],
+        }
+    }
+}
+
+// Cells of `open` the cat on `from` can reach
+fn reachable(open: u64, from: usize) -> u64 {
+    let mut seen = 1u64 << from;
+    let mut frontier = seen;
+    while frontier != 0 {
+        let mut next = 0;
+        for bit in bits(frontier) {
+            next |= SQUARE_NEIGHBORS[bit];
+        }
+        frontier = next & open & !seen;
+        seen |= frontier;
+    }
+    seen
+}
+
+// Walls the walls need to cut a region off from the edge, their move, against
+// the most stubborn cat; None if the cat gets out
+fn solve(key: Key, memo: &mut HashMap<Key, Option<u8>>) -> Option<u8> {
+    if let Some(&value) = memo.get(&key) {
+        return value;
+    }
+    let cat = key.cat as usize;
+    let mut best: Option<u8> = None;
+    for wall in bits(key.cells & !(1 << cat)) {
+        let open = reachable(key.cells & !(1 << wall), cat);
+        if open & key.edge == 0 {
+            best = Some(1);
+            break;
+        }
+        // The cat's longest answer
+        let mut worst = Some(0);
+        for reply in bits(SQUARE_NEIGHBORS[cat] & open) {
+            let value = if key.edge >> reply & 1 != 0 {
+                None
+            } else {
+                solve(key.with(open, reply), memo)
+            };
+            match value {
+                Some(value) => worst = worst.max(Some(value)),
+                None => {
+                    worst = None;
+                    break;
+                }
+            }
+        }
+        if let Some(worst) = worst {
+            best = Some(best.map_or(worst + 1, |b| b.min(worst + 1)));
+        }
+    }
+    memo.insert(key, best);
+    best
+}
+
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    max_size: usize,
+    entries: Vec<(Key, u8)>, // Won regions, sorted by key, with the walls they take
+}
+
+impl Tablebase {
+    // Solves every region of up to `max_size` cells (at most MAX_SIZE) that
+    // reaches the edge of the board, with the cat on any of its inner cells
+    pub fn generate(max_size: usize) -> Tablebase {
+        let max_size = max_size.min(MAX_SIZE);
+        let hex = Topology::hex();
+        let neighbors: Vec<u128> = (0..N)
+            .map(|i| {
+                hex.neighbors(Position::from_idx(i))
+                    .iter()
+                    .fold(0, |mask, q| mask | 1 << q.idx())
+            })
+            .collect();
+        let edge: u128 = hex.exits().fold(0, |mask, p| mask | 1 << p.idx());
+
+        let mut memo = HashMap::new();
+        let mut visit = |region: u128| {
+            if region & edge == 0 {
+                return;
+            }
+            let cells: Vec<(Axial, bool)> = bits128(region)
+                .map(Position::from_idx)
+                .map(|p| (Axial::from(p), hex.is_exit(p)))
+                .collect();
+            for cat in bits128(region & !edge).map(Position::from_idx) {
+                solve(Key::new(&cells, Axial::from(cat)), &mut memo);
+            }
+        };
+        for anchor in 0..N {
+            let above: u128 = !0 << anchor << 1;
+            let region = 1u128 << anchor;
+            let border = region | neighbors[anchor];
+            connected_sets(
+                region,
+                border,
+                neighbors[anchor] & above,
+                above,
+                max_size,
+                &neighbors,
+                &mut visit,
+            );
+        }
+
+        let mut entries: Vec<(Key, u8)> = memo
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|v| (key, v)))
+            .collect();
+        entries.sort();
+        Tablebase { max_size, entries }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Walls needed to trap the cat from `board`, the walls to move, whatever
+    // the cat does; None if the position is not a win in the table
+    pub fn probe(&self, board: &Board) -> Option<usize> {
+        if board.topology.kind() != Kind::Hex || board.has_cat_escaped() || board.cat_region().size() > self.max_size {
+            return None;
+        }
+        let key = Key::of_board(board)?;
+        let i = self.entries.binary_search_by_key(&key, |&(k, _)| k).ok()?;
+        Some(self.entries[i].1 as usize)
+    }
+
+    // The table's winning line from `board` against `move_cat`, each wall
+    // keeping the table's promise against every reply
+    pub fn plan(&self, board: &Board) -> Option<Plan> {
+        let mut value = self.probe(board)?;
+        let mut board = board.clone();
+        let mut plan = Plan::new();
+        loop {
+            let region = board.cat_region();
+            let wall = region
+                .cells
+                .iter()
+                .copied()
+                .filter(|&p| p != board.cat_position)
+                .find(|&p| self.after_wall(&board, p).is_some_and(|v| v <= value))?;
+            board.move_player(wall);
+            if board.min_cat_moves() == N {
+                plan.push(wall, None);
+                return Some(plan);
+            }
+            let reply = board.move_cat();
+            plan.push(wall, reply);
+            value = self.probe(&board)?;
+        }
+    }
+
+    // Walls to trap the cat counting `wall`, against its longest answer
+    fn after_wall(&self, board: &Board, wall: Position) -> Option<usize> {
+        let mut next = board.clone();
+        next.cells.set(wall.idx(), true);
+        if next.min_cat_moves() == N {
+            return Some(1);
+        }
+        let mut worst = 0;
+        for &reply in next.neighbors(next.cat_position) {
+            if next.cells[reply.idx()] {
+                continue;
+            }
+            let mut after = next.clone();
+            after.cat_position = reply;
+            worst = worst.max(self.probe(&after)?);
+        }
+        Some(worst + 1)
+    }
+
+    // Header (magic, largest region, entry count) then 18 bytes per entry
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&[self.max_size as u8])?;
+        out.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for &(key, value) in &self.entries {
+            out.write_all(&key.to_bytes())?;
+            out.write_all(&[value])?;
+        }
+        out.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Tablebase> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut input = BufReader::new(File::open(path)?);
+        let mut header = [0; 11];
+        input.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("Not a tablebase file"));
+        }
+        let max_size = header[6] as usize;
+        if max_size > MAX_SIZE {
+            return Err(invalid("Tablebase regions too large"));
+        }
+        let count = u32::from_le_bytes(header[7..].try_into().expect("4 bytes")) as usize;
+        let mut entries = Vec::with_capacity(count);
+        let mut entry = [0; 18];
+        for _ in 0..count {
+            input.read_exact(&mut entry)?;
+            let key = Key::from_bytes(entry[..17].try_into().expect("17 bytes"));
+            entries.push((key, entry[17]));
+        }
+        if !entries.is_sorted_by_key(|&(key, _)| key) {
+            return Err(invalid("Tablebase entries out of order"));
+        }
+        Ok(Tablebase { max_size, entries })
+    }
+}
+
+// Every connected set of cells grown from `region` by cells of `extension`
+// and their neighbours in `allowed`, visiting each set once (ESU enumeration)
+fn connected_sets(
+    region: u128,
+    border: u128, // The region and its neighbours
+    mut extension: u128,
+    allowed: u128,
+    max_size: usize,
+    neighbors: &[u128],
+    visit: &mut dyn FnMut(u128),
+) {
+    visit(region);
+    if region.count_ones() as usize == max_size {
+        return;
+    }
+    while extension != 0 {
+        let cell = extension.trailing_zeros() as usize;
+        extension &= extension - 1;
+        let exclusive = neighbors[cell] & !border & allowed;
+        connected_sets(
+            region | 1 << cell,
+            border | neighbors[cell],
+            extension | exclusive,
+            allowed,
+            max_size,
+            neighbors,
+            visit,
+        );
+    }
+}
+
+fn bits128(mut mask: u128) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let bit = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            bit
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::strategies::RandomCat;
+    use crate::{AutoPlayer, CatPlayer, DefaultCat, SearchBudget};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    // Grows a region of two to six cells from a random edge cell and walls
+    // everything else, with the cat on its first inner cell; None if it has
+    // no inner cell
+    fn walled_region(seed: u64, rng: &mut Rng) -> Option<Board> {
+        let mut region = vec![Position::from_idx(rng.below(N))];
+        let hex = Topology::hex();
+        while !hex.is_exit(region[0]) {
+            region[0] = Position::from_idx(rng.below(N));
+        }
+        let size = 2 + rng.below(5);
+        while region.len() < size {
+            let p = region[rng.below(region.len())];
+            let q = hex.neighbors(p)[rng.below(hex.neighbors(p).len())];
+            if !region.contains(&q) {
+                region.push(q);
+            }
+        }
+        let &cat = region.iter().find(|&&p| !hex.is_exit(p))?;
+        let mut board = Board::random(seed);
+        for i in 0..N {
+            board.cells.set(i, !region.contains(&Position::from_idx(i)));
+        }
+        board.cat_position = cat;
+        Some(board)
+    }
+
+    // Small regions walled off near the edge: the table's lines trap the
+    // cat within the promised number of walls, whatever it does
+    #[test]
+    fn table_lines_trap_any_cat() {
+        let table = Tablebase::generate(6);
+        let mut rng = Rng::new(11);
+        let mut probed = 0;
+        for seed in 0..300 {
+            let Some(board) = walled_region(seed, &mut rng) else {
+                continue;
+            };
+            let Some(value) = table.probe(&board) else {
+                continue;
+            };
+            probed += 1;
+            let plan = table.plan(&board).expect("a won position has a line");
+            assert!(plan.len() <= value, "seed {}\n{}", seed, board);
+            assert_eq!(
+                plan.verify(&board, &mut DefaultCat).ok(),
+                Some(plan.len()),
+                "seed {}\n{}",
+                seed,
+                board
+            );
+            for cat_seed in 0..5 {
+                // Replan after every reply
+                let mut game = board.clone();
+                let mut walls = 0;
+                let mut cat = RandomCat::new(cat_seed);
+                while game.min_cat_moves() != N {
+                    let step = table.plan(&game).and_then(|plan| plan.next()).expect("still won");
+                    game.move_player(step.wall);
+                    walls += 1;
+                    if game.min_cat_moves() == N {
+                        break;
+                    }
+                    let reply = cat.get_move(&game).expect("a free cat can move");
+                    game.cat_position = reply;
+                    assert!(!game.has_cat_escaped(), "seed {} cat {}\n{}", seed, cat_seed, board);
+                }
+                assert!(walls <= value, "seed {} cat {}\n{}", seed, cat_seed, board);
+            }
+        }
+        assert!(probed > 0);
+    }
+
+    // A search holding the table stops at the first won region it meets,
+    // here the root, and plays the table's line. Regions a single wall
+    // closes are left out, since the search proves those on its own.
+    #[test]
+    fn search_plays_the_table_line() {
+        let table = Arc::new(Tablebase::generate(6));
+        let budget = SearchBudget {
+            nodes: Some(1),
+            ..SearchBudget::default()
+        };
+        let mut rng = Rng::new(11);
+        let mut searched = 0;
+        for seed in 0..300 {
+            let Some(board) = walled_region(seed, &mut rng) else {
+                continue;
+            };
+            if table.probe(&board).is_none() || board.min_vertex_cut().size < 2 {
+                continue;
+            }
+            searched += 1;
+            let player = AutoPlayer {
+                tablebase: Some(table.clone()),
+                ..AutoPlayer::with_budget(budget)
+            };
+            let result = player.search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+            assert_eq!(result.solution, table.plan(&board), "seed {}\n{}", seed, board);
+            let without = AutoPlayer::with_budget(budget).search(&board, budget, &AtomicBool::new(false), &mut |_| {});
+            assert!(!without.is_proven(), "seed {}\n{}", seed, board);
+        }
+        assert!(searched > 0);
+    }
+
+    // A region and its mirror image share a key
+    #[test]
+    fn keys_fold_symmetries() {
+        let cells =
+            [(0, 0, true), (1, 0, false), (1, 1, false), (2, 1, true)].map(|(q, r, edge)| (Axial::new(q, r), edge));
+        let cat = Axial::new(1, 0);
+        let key = Key::new(&cells, cat);
+        for t in 0..12 {
+            let moved: Vec<_> = cells.iter().map(|&(a, edge)| (a.transform(t), edge)).collect();
+            assert_eq!(Key::new(&moved, cat.transform(t)), key);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+// --- Board topologies ---
+//
+// Which cells are next to which, and which cells the cat escapes from. The
+// game is played on the offset hex grid, but a variant can be tried by giving
+// a board another topology instead of forking the code. In the board's text
+// form it is named on a first line "topology <spec>", and in its notation as
+// a first "<spec>/" (see Board::from_reader and Board::from_notation):
+//
+//   hex                  six neighbours, odd rows shifted right, exits all
+//                        around the edge (the default)
+//   square4              four neighbours, exits all around the edge
+//   square8              eight neighbours, diagonals included, same exits
+//   wrap:r,c;r,c;...     the hex grid with its columns wrapped around, and
+//                        exits only at the listed cells
+//
+// Only the columns of the hex grid wrap: with 11 rows, the last and the first
+// would both be even and their cells would not line up.
+//
+// Every board carries its topology, shared behind an Arc, and neighbour lists
+// are worked out once per topology. Board::neighbors and Board::is_exit read
+// them, so the BFS, move_cat, the cut, the searches, the parser and the
+// renderers all follow the board's topology, and the order of each list is the
+// order the cat breaks ties in. The tablebase, the opening book and the
+// direction names belong to the plain hex grid.
+
+use crate::field::Grid;
+use crate::{Direction, Int, Position, C, N, R};
+use once_cell::sync::Lazy;
+use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
+
+// Most neighbours a cell can have, in any topology
+pub const MAX_DEGREE: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Hex,
+    Square4,
+    Square8,
+    Wrap,
+}
+
+// Square steps as (row, column) offsets, clockwise from the west like
+// Direction::ALL
+const SQUARE4: [(Int, Int); 4] = [(0, -1), (-1, 0), (0, 1), (1, 0)];
+const SQUARE8: [(Int, Int); 8] = [(0, -1), (-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1)];
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Topology {
+    kind: Kind,
+    exits: Grid<bool>,
+    degree: Grid<u8>,
+    neighbors: Grid<[Position; MAX_DEGREE]>, // The first `degree`, in tie-break order
+    back: Grid<[u8; MAX_DEGREE]>,            // Where the cell is in each neighbour's list
+}
+
+// The plain hex grid, shared by every board that does not choose another
+static HEX: Lazy<Arc<Topology>> = Lazy::new(|| Arc::new(Topology::new(Kind::Hex, &[])));
+
+impl Topology {
+    pub fn hex() -> Arc<Topology> {
+        HEX.clone()
+    }
+
+    // A topology given by its spec, as listed above
+    pub fn parse(spec: &str) -> Result<Topology, String> {
+        let (name, exits) = match spec.trim().split_once(':') {
+            Some((name, exits)) => (name, Some(exits)),
+            None => (spec.trim(), None),
+        };
+        match (name, exits) {
+            ("hex", None) => Ok(Topology::new(Kind::Hex, &[])),
+            ("square4", None) => Ok(Topology::new(Kind::Square4, &[])),
+            ("square8", None) => Ok(Topology::new(Kind::Square8, &[])),
+            ("wrap", Some(exits)) => {
+                let exits = exits
+                    .split(';')
+                    .map(parse_exit)
+                    .collect::<Result<Vec<Position>, String>>()?;
+                Ok(Topology::new(Kind::Wrap, &exits))
+            }
+            ("wrap", None) => Err("The wrap topology needs its exits, as in wrap:0,0;10,9".to_string()),
+            _ => Err(format!(
+                "Unknown topology '{}' (expected hex, square4, square8 or wrap:r,c;...)",
+                spec.trim()
+            )),
+        }
+    }
+
+    // `exits` are only read for Kind::Wrap; the others escape at the edge
+    pub fn new(kind: Kind, exits: &[Position]) -> Topology {
+        let cells: Vec<Position> = (0..N).map(Position::from_idx).collect();
+        let mut exit = Grid::new(false);
+        for &p in &cells {
+            exit[p] = match kind {
+                Kind::Wrap => exits.contains(&p),
+                _ => p.r == 0 || p.r == R - 1 || p.c == 0 || p.c == C - 1,
+            };
+        }
+
+        // Fixed-size rows rather than a Vec per cell, since the search reads
+        // these on every node
+        let mut topology = Topology {
+            kind,
+            exits: exit,
+            degree: Grid::new(0),
+            neighbors: Grid::new([Position::new(0, 0); MAX_DEGREE]),
+            back: Grid::new([0; MAX_DEGREE]),
+        };
+        for &p in &cells {
+            let steps: Vec<Position> = match kind {
+                Kind::Hex => Direction::ALL.iter().map(|&d| p.step(d)).collect(),
+                Kind::Wrap => Direction::ALL.iter().map(|&d| wrap_column(p.step(d))).collect(),
+                Kind::Square4 => square_steps(p, &SQUARE4),
+                Kind::Square8 => square_steps(p, &SQUARE8),
+            };
+            for q in steps.into_iter().filter(Position::is_valid) {
+                topology.neighbors[p][topology.degree[p] as usize] = q;
+                topology.degree[p] += 1;
+            }
+        }
+        for &p in &cells {
+            for slot in 0..topology.degree[p] as usize {
+                let q = topology.neighbors[p][slot];
+                let back = topology.neighbors(q).iter().position(|&r| r == p);
+                topology.back[p][slot] = back.expect("Neighbours should be mutual") as u8;
+            }
+        }
+        topology
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn neighbors(&self, p: Position) -> &[Position] {
+        &self.neighbors[p][..self.degree[p] as usize]
+    }
+
+    // Where `p` is in the list of its `slot`th neighbour
+    pub fn back(&self, p: Position, slot: usize) -> usize {
+        self.back[p][slot] as usize
+    }
+
+    pub fn is_exit(&self, p: Position) -> bool {
+        self.exits[p]
+    }
+
+    // The cells the cat escapes from, in board order
+    pub fn exits(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..N).map(Position::from_idx).filter(|&p| self.exits[p])
+    }
+
+    // Whether row `r` is drawn, and read, half a cell to the right
+    pub fn is_shifted(&self, r: Int) -> bool {
+        matches!(self.kind, Kind::Hex | Kind::Wrap) && r % 2 != 0
+    }
+
+    // The neighbour in direction `dir`, on the grids that have directions
+    pub fn step(&self, p: Position, dir: Direction) -> Option<Position> {
+        match self.kind {
+            Kind::Hex => Some(p.step(dir)),
+            Kind::Wrap => Some(wrap_column(p.step(dir))),
+            Kind::Square4 | Kind::Square8 => None,
+        }
+    }
+}
+
+impl Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Hex => write!(f, "hex"),
+            Kind::Square4 => write!(f, "square4"),
+            Kind::Square8 => write!(f, "square8"),
+            Kind::Wrap => {
+                let exits: Vec<String> = self.exits().map(|p| format!("{},{}", p.r, p.c)).collect();
+                write!(f, "wrap:{}", exits.join(";"))
+            }
+        }
+    }
+}
+
+// Just the spec: the neighbour tables would bury any board they are part of
+impl Debug for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Topology({})", self)
+    }
+}
+
+fn square_steps(p: Position, offsets: &[(Int, Int)]) -> Vec<Position> {
+    offsets
+        .iter()
+        .map(|&(dr, dc)| Position::new(p.r + dr, p.c + dc))
+        .collect()
+}
+
+fn wrap_column(p: Position) -> Position {
+    Position::new(p.r, p.c.rem_euclid(C))
+}
+
+fn parse_exit(cell: &str) -> Result<Position, String> {
+    let invalid = || format!("Invalid exit '{}' (expected r,c on the board)", cell);
+    let (r, c) = cell.trim().split_once(',').ok_or_else(invalid)?;
+    let p = match (r.trim().parse::<Int>(), c.trim().parse::<Int>()) {
+        (Ok(r), Ok(c)) => Position::new(r, c),
+        _ => return Err(invalid()),
+    };
+    p.is_valid().then_some(p).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    // Neighbours are mutual, `back` finds the way back, and each grid has
+    // the degree it should away from the edge
+    #[test]
+    fn neighbours_are_mutual() {
+        let specs = ["hex", "square4", "square8", "wrap:0,0;5,9"];
+        for (spec, degree) in specs.into_iter().zip([6, 4, 8, 6]) {
+            let topology = Topology::parse(spec).unwrap();
+            assert_eq!(topology.to_string(), spec);
+            for p in (0..N).map(Position::from_idx) {
+                for (slot, &q) in topology.neighbors(p).iter().enumerate() {
+                    assert_eq!(topology.neighbors(q)[topology.back(p, slot)], p, "{} at {}", spec, p);
+                }
+                let inner = p.r > 0 && p.r < R - 1 && p.c > 0 && p.c < C - 1;
+                if inner || (topology.kind() == Kind::Wrap && p.r > 0 && p.r < R - 1) {
+                    assert_eq!(topology.neighbors(p).len(), degree, "{} at {}", spec, p);
+                }
+            }
+        }
+    }
+
+    fn cells(list: &[(Int, Int)]) -> Vec<Position> {
+        list.iter().map(|&(r, c)| Position::new(r, c)).collect()
+    }
+
+    // Neighbours in tie-break order, clipped at the edge or wrapped around it
+    #[test]
+    fn neighbour_lists() {
+        let hex = Topology::parse("hex").unwrap();
+        let square4 = Topology::parse("square4").unwrap();
+        let square8 = Topology::parse("square8").unwrap();
+        let wrap = Topology::parse("wrap:0,0; 5,9").unwrap();
+        let at = |r, c| Position::new(r, c);
+
+        assert_eq!(
+            hex.neighbors(at(4, 5)),
+            cells(&[(4, 4), (3, 4), (3, 5), (4, 6), (5, 5), (5, 4)])
+        );
+        assert_eq!(
+            hex.neighbors(at(5, 5)),
+            cells(&[(5, 4), (4, 5), (4, 6), (5, 6), (6, 6), (6, 5)])
+        );
+        assert_eq!(hex.neighbors(at(4, 0)), cells(&[(3, 0), (4, 1), (5, 0)]));
+        assert_eq!(square4.neighbors(at(5, 5)), cells(&[(5, 4), (4, 5), (5, 6), (6, 5)]));
+        assert_eq!(square4.neighbors(at(0, 0)), cells(&[(0, 1), (1, 0)]));
+        assert_eq!(
+            square8.neighbors(at(5, 5)),
+            cells(&[(5, 4), (4, 4), (4, 5), (4, 6), (5, 6), (6, 6), (6, 5), (6, 4)])
+        );
+        assert_eq!(square8.neighbors(at(0, 0)), cells(&[(0, 1), (1, 1), (1, 0)]));
+        assert_eq!(
+            square8.neighbors(at(R - 1, C - 1)),
+            cells(&[(R - 1, C - 2), (R - 2, C - 2), (R - 2, C - 1)])
+        );
+        // West of the first column is the last one; the rows still end
+        assert_eq!(
+            wrap.neighbors(at(4, 0)),
+            cells(&[(4, 9), (3, 9), (3, 0), (4, 1), (5, 0), (5, 9)])
+        );
+        assert_eq!(wrap.neighbors(at(0, 9)), cells(&[(0, 8), (0, 0), (1, 9), (1, 8)]));
+        assert_eq!(wrap.step(at(4, 0), Direction::West), Some(at(4, C - 1)));
+        assert_eq!(square4.step(at(4, 0), Direction::West), None);
+    }
+
+    #[test]
+    fn exit_cells() {
+        let border: Vec<Position> = (0..N)
+            .map(Position::from_idx)
+            .filter(|p| p.r == 0 || p.r == R - 1 || p.c == 0 || p.c == C - 1)
+            .collect();
+        assert_eq!(border.len(), 38);
+        for spec in ["hex", "square4", "square8"] {
+            let exits: Vec<Position> = Topology::parse(spec).unwrap().exits().collect();
+            assert_eq!(exits, border, "{}", spec);
+        }
+        let wrap = Topology::parse("wrap:5,9;0,0").unwrap();
+        assert_eq!(wrap.exits().collect::<Vec<_>>(), cells(&[(0, 0), (5, 9)]));
+        assert!(!wrap.is_exit(Position::new(4, 0)));
+
+        assert!(Topology::parse("wrap").is_err());
+        assert!(Topology::parse("wrap:11,0").is_err());
+        assert!(Topology::parse("triangle").is_err());
+    }
+
+    // The topology survives the board's text and notation, and the cat
+    // runs along its neighbours to its exits
+    #[test]
+    fn boards_carry_their_topology() {
+        let mut board = Board::new();
+        board.topology = Arc::new(Topology::parse("wrap:4,9").unwrap());
+        board.cat_position = Position::new(4, 0);
+        board.cells.set(Position::new(4, 1).idx(), true);
+
+        let text = board.to_string();
+        assert!(text.starts_with("topology wrap:4,9\n"), "{}", text);
+        let read = Board::from_reader(&mut text.as_bytes()).unwrap();
+        let notation = board.to_notation();
+        assert!(notation.starts_with("wrap:4,9/"), "{}", notation);
+        for copy in [read, Board::from_notation(&notation).unwrap()] {
+            assert_eq!(copy.topology, board.topology);
+            assert_eq!(copy.cells, board.cells);
+            assert_eq!(copy.cat_position, board.cat_position);
+        }
+        assert_eq!(board.min_cat_moves(), 1);
+        assert_eq!(board.move_cat(), Some(Position::new(4, 9)));
+        assert!(board.has_cat_escaped());
+
+        // Only a diagonal is left open, and only square8 has diagonals
+        for (spec, escapes) in [("square4", false), ("square8", true)] {
+            let mut board = Board::new();
+            board.topology = Arc::new(Topology::parse(spec).unwrap());
+            board.cat_position = Position::new(1, 1);
+            for p in cells(&[(1, 0), (0, 1), (2, 1), (1, 2), (2, 0), (0, 2), (2, 2)]) {
+                board.cells.set(p.idx(), true);
+            }
+            assert_eq!(board.min_cat_moves() == 1, escapes, "{}", spec);
+            if escapes {
+                assert_eq!(board.move_cat(), Some(Position::new(0, 0)));
+            }
+        }
+        assert_eq!(Board::new().to_notation().split('/').count(), R as usize);
+    }
+}
@@ -0,0 +1,239 @@
+// --- Tournament runner ---
+//
+// Plays every wall strategy against every cat strategy on the same seeded set
+// of starting boards and reports win rates with 95% confidence intervals, the
+// average number of walls in won games, and an Elo-style ranking fitted over
+// all games.
+
+use crate::game::{play_game, GameStatus};
+use crate::strategies::{cat_player, wall_player, wall_strategy_error, Tables, CAT_STRATEGIES, WALL_STRATEGIES};
+use crate::Board;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub walls: Vec<String>,
+    pub cats: Vec<String>,
+    pub boards: usize,
+    pub seed: u64,
+    pub tables: Tables,
+}
+
+impl Config {
+    // Parses "--walls a,b --cats x,y --boards N --seed S"; everything is optional
+    pub fn from_args(args: &[String], tables: &Tables) -> Result<Config, String> {
+        let mut config = Config {
+            // The book player only plays with a book
+            walls: WALL_STRATEGIES
+                .iter()
+                .filter(|&&s| s != "book" || tables.book.is_some())
+                .map(|s| s.to_string())
+                .collect(),
+            cats: CAT_STRATEGIES.iter().map(|s| s.to_string()).collect(),
+            boards: 20,
+            seed: 0,
+            tables: tables.clone(),
+        };
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let value = iter.next().ok_or(format!("Missing value for {}", flag))?;
+            let list = || value.split(',').map(str::to_string).collect::<Vec<_>>();
+            match flag.as_str() {
+                "--walls" => config.walls = list(),
+                "--cats" => config.cats = list(),
+                "--boards" => config.boards = value.parse().map_err(|_| format!("Invalid --boards '{}'", value))?,
+                "--seed" => config.seed = value.parse().map_err(|_| format!("Invalid --seed '{}'", value))?,
+                _ => return Err(format!("Unknown option '{}'", flag)),
+            }
+        }
+        if let Some(name) = config.walls.iter().find(|n| wall_player(n, 0, tables).is_none()) {
+            return Err(wall_strategy_error(name, tables));
+        }
+        if let Some(name) = config.cats.iter().find(|n| cat_player(n, 0).is_none()) {
+            return Err(format!("Unknown cat strategy '{}'", name));
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Pairing {
+    pub walls: String,
+    pub cat: String,
+    pub games: usize,
+    pub wins: usize, // Won by the wall player
+    pub walls_in_wins: usize,
+    pub forfeits: usize,
+}
+
+impl Pairing {
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    pub fn average_walls(&self) -> Option<f64> {
+        (self.wins > 0).then(|| self.walls_in_wins as f64 / self.wins as f64)
+    }
+}
+
+// Wilson score interval for a binomial proportion at 95% confidence
+pub fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let z = 1.96;
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let denominator = 1.0 + z * z / n;
+    let centre = (p + z * z / (2.0 * n)) / denominator;
+    let margin = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+pub struct Tournament {
+    pub pairings: Vec<Pairing>,
+    pub ratings: Vec<(String, f64)>, // Best first
+}
+
+pub fn run(config: &Config) -> Tournament {
+    let mut pairings = Vec::new();
+    for walls in &config.walls {
+        for cat in &config.cats {
+            let mut pairing = Pairing {
+                walls: walls.clone(),
+                cat: cat.clone(),
+                ..Pairing::default()
+            };
+            for i in 0..config.boards {
+                let seed = config.seed.wrapping_add(i as u64);
+                let mut wall_bot = wall_player(walls, seed, &config.tables).expect("checked in Config::from_args");
+                let mut cat_bot = cat_player(cat, seed).expect("checked in Config::from_args");
+                let result = play_game(Board::random(seed), wall_bot.as_mut(), cat_bot.as_mut());
+                pairing.games += 1;
+                if result.forfeit.is_some() {
+                    pairing.forfeits += 1;
+                }
+                if result.status == GameStatus::Won {
+                    pairing.wins += 1;
+                    pairing.walls_in_wins += result.walls();
+                }
+            }
+            eprintln!("{} vs {}: {}/{} won", walls, cat, pairing.wins, pairing.games);
+            pairings.push(pairing);
+        }
+    }
+    let ratings = elo_ratings(&pairings);
+    Tournament { pairings, ratings }
+}
+
+// Bradley-Terry strengths fitted with the MM algorithm, on the Elo scale
+// (mean 1500). Each pairing gets one virtual draw so unbeaten players still
+// get a finite rating.
+fn elo_ratings(pairings: &[Pairing]) -> Vec<(String, f64)> {
+    let mut names: Vec<String> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut id = |name: String| {
+        *index.entry(name.clone()).or_insert_with(|| {
+            names.push(name);
+            names.len() - 1
+        })
+    };
+    // (wall player, cat, games, wall player's score), including the virtual draw
+    let games: Vec<(usize, usize, f64, f64)> = pairings
+        .iter()
+        .map(|p| {
+            let a = id(format!("walls:{}", p.walls));
+            let b = id(format!("cat:{}", p.cat));
+            (a, b, p.games as f64 + 1.0, p.wins as f64 + 0.5)
+        })
+        .collect();
+
+    let mut strength = vec![1.0; names.len()];
+    for _ in 0..500 {
+        let mut wins = vec![0.0; names.len()];
+        let mut denominators = vec![0.0; names.len()];
+        for &(a, b, n, score_a) in &games {
+            wins[a] += score_a;
+            wins[b] += n - score_a;
+            let d = n / (strength[a] + strength[b]);
+            denominators[a] += d;
+            denominators[b] += d;
+        }
+        for i in 0..strength.len() {
+            strength[i] = wins[i] / denominators[i];
+        }
+        let log_mean = strength.iter().map(|s: &f64| s.ln()).sum::<f64>() / strength.len() as f64;
+        for s in &mut strength {
+            *s /= log_mean.exp();
+        }
+    }
+
+    let mut ratings: Vec<(String, f64)> = names
+        .into_iter()
+        .zip(strength)
+        .map(|(name, s)| (name, 1500.0 + 400.0 * s.log10()))
+        .collect();
+    ratings.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ratings
+}
+
+pub fn print_report(tournament: &Tournament) {
+    println!(
+        "{:<10} {:<10} {:>6} {:>7} {:>17} {:>10} {:>9}",
+        "Walls", "Cat", "Games", "Win%", "95% CI", "Avg walls", "Forfeits"
+    );
+    for p in &tournament.pairings {
+        let (low, high) = wilson_interval(p.wins, p.games);
+        println!(
+            "{:<10} {:<10} {:>6} {:>6.1}% {:>17} {:>10} {:>9}",
+            p.walls,
+            p.cat,
+            p.games,
+            100.0 * p.win_rate(),
+            format!("[{:.1}%, {:.1}%]", 100.0 * low, 100.0 * high),
+            p.average_walls().map_or("-".to_string(), |w| format!("{:.1}", w)),
+            p.forfeits
+        );
+    }
+    println!();
+    println!("Ranking (Elo):");
+    for (i, (name, rating)) in tournament.ratings.iter().enumerate() {
+        println!("{:>3}. {:<16} {:>6.0}", i + 1, name, rating);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn wilson_interval_on_fixed_counts() {
+        assert_eq!(wilson_interval(0, 0), (0.0, 1.0));
+        let (low, high) = wilson_interval(50, 100);
+        assert!(close(low, 0.4038) && close(high, 0.5962), "{} {}", low, high);
+        let (low, high) = wilson_interval(10, 10);
+        assert!(close(low, 0.7225) && high == 1.0, "{} {}", low, high);
+    }
+
+    // With one pairing the ratings sit either side of 1500, apart by the Elo
+    // difference of the score including the virtual draw (30.5 to 10.5)
+    #[test]
+    fn elo_ratings_on_fixed_counts() {
+        let pairing = Pairing {
+            walls: "auto".to_string(),
+            cat: "default".to_string(),
+            games: 40,
+            wins: 30,
+            ..Pairing::default()
+        };
+        let ratings = elo_ratings(&[pairing]);
+        assert_eq!(ratings[0].0, "walls:auto");
+        assert_eq!(ratings[1].0, "cat:default");
+        assert!(close(ratings[0].1 + ratings[1].1, 3000.0));
+        assert!(close(ratings[0].1 - ratings[1].1, 400.0 * (30.5f64 / 10.5).log10()));
+    }
+}